@@ -4,13 +4,14 @@ use html::{Escape, Template};
 #[derive(Default, Debug, Clone)]
 pub struct Params {name: String}
 
-impl FromIterator<(String, String)> for Params {
-    fn from_iter<T: IntoIterator<Item = (String, String)>>(iter: T) -> Self {
+impl ph::TryFromParams for Params {
+    fn try_from_params(params: impl IntoIterator<Item = (String, String)>) -> Result<Self, HttpError> {
         let mut ret = Self::default();
-        for (key, value) in iter {
+        for (key, value) in params {
             if key == "name" { ret.name = value; }
         }
-        ret
+        if "" == ret.name { return Err(HttpError::Invalid("Please tell me your name.".into())); }
+        Ok(ret)
     }
 }
 
@@ -47,16 +48,14 @@ impl<'a> ph::Handle for Greet<'a> {
 
     fn handle_get(
         &mut self,
+        _context: &ph::Context,
         _path: &[String],
         params: Self::Params,
     ) -> ph::Result {
-        if "" != params.name {
-            if self.0.visitors.contains_key(&params.name) {
-                return Ok(HttpOkay::Html(self.greet(params)));
-            } else {
-                return Ok(HttpOkay::Html(self.introduce(params)));
-            }
+        if self.0.visitors.contains_key(&params.name) {
+            Ok(HttpOkay::Html(self.greet(params)))
+        } else {
+            Ok(HttpOkay::Html(self.introduce(params)))
         }
-        Err(HttpError::Invalid)
     }
 }
@@ -1,6 +1,6 @@
 use std::collections::{HashMap};
 
-use petite_http::{self as ph, html, include_html, HttpOkay, HttpError};
+use petite_http::{self as ph, include_html, HttpOkay, HttpError, Method};
 use ph::content_types::{CSS};
 use ph::html::{Escape, Concat, Template};
 
@@ -18,12 +18,12 @@ pub struct Demo {
 }
 
 impl Demo {
-    fn guest(name: &String) -> Box<dyn Escape> {
+    fn guest(name: &str) -> Box<dyn Escape> {
         Box::new(Template(
             r#"<li><a href="/visitor/{url_name}">{name}</a></li>"#,
             Box::new([
-                ("name", Box::new(name.clone())),
-                ("url_name", Box::new(name.clone())), // FIXME: URL-encode
+                ("name", Box::new(name.to_owned())),
+                ("url_name", Box::new(name.to_owned())), // FIXME: URL-encode
             ]),
         ))
     }
@@ -32,7 +32,7 @@ impl Demo {
         Box::new(Template(
             include_str!("guest_book.html"),
             Box::new([
-                ("visitors", Box::new(Concat(self.visitors.keys().map(Self::guest).collect()))),
+                ("visitors", Box::new(Concat(self.visitors.keys().map(|name| Self::guest(name)).collect()))),
             ]),
         ))
     }
@@ -51,8 +51,12 @@ impl Demo {
 }
 
 impl ph::Route for Demo {
-    fn route(&mut self, path: &[String], callback: impl ph::Callback) -> ph::Result {
-        let mut path_iter = path.into_iter();
+    fn route(&mut self, context: &ph::Context, path: &[String], callback: impl ph::Callback) -> ph::Result {
+        if !matches!(path.first().map(String::as_str), Some("greet" | "thank")) {
+            // `callback` is not called for the other pages, so check the method here.
+            if let Some(response) = context.check_method(&[Method::Get, Method::Head])? { return Ok(response); }
+        }
+        let mut path_iter = path.iter();
         let Some(page) = path_iter.next() else {
             return Ok(HttpOkay::Redirect("start".into()))
         };
@@ -66,7 +70,7 @@ impl ph::Route for Demo {
             return callback.handle_with(&mut Thank(self));
         } else if page == "visitor" {
             let Some(name) = path_iter.next() else {
-                return Err(HttpError::Invalid)
+                return Err(HttpError::Invalid("Whose page?".into()))
             };
             if self.visitors.contains_key(name) {
                 return Ok(HttpOkay::Html(self.visitor(name.into())));
@@ -74,12 +78,28 @@ impl ph::Route for Demo {
                 return Err(HttpError::NotFound)
             }
         }
-        return Err(HttpError::Invalid);
+        Err(HttpError::Invalid(format!("There is no page called {:?}.", page)))
+    }
+
+    fn render_error(&mut self, error: &HttpError) -> Option<HttpOkay> {
+        let message = match error {
+            HttpError::Invalid(message) => message.clone(),
+            HttpError::NotFound => "Sorry, I don't know who that is.".into(),
+            _ => return None,
+        };
+        Some(HttpOkay::Html(Box::new(Template(
+            include_str!("error.html"),
+            Box::new([
+                ("message", Box::new(message)),
+            ]),
+        ))))
     }
 }
 
 // ----------------------------------------------------------------------------
 
-fn main() {
-    ph::start("localhost:8080".into(), None, Demo::default());
+fn main() -> Result<(), ph::StartError> {
+    let demo = ph::start("localhost:8080".into(), None, Demo::default())?;
+    println!("Forgetting {} visitors", demo.visitors.len());
+    Ok(())
 }
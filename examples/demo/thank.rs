@@ -3,34 +3,47 @@ use petite_http::{self as ph, HttpOkay, HttpError, include_html};
 #[derive(Default, Debug, Clone)]
 pub struct Params {name: String, greeting: String}
 
-impl FromIterator<(String, String)> for Params {
-    fn from_iter<T: IntoIterator<Item = (String, String)>>(iter: T) -> Self {
+impl ph::TryFromParams for Params {
+    fn try_from_params(params: impl IntoIterator<Item = (String, String)>) -> Result<Self, HttpError> {
         let mut ret = Self::default();
-        for (key, value) in iter {
+        for (key, value) in params {
             if key == "name" { ret.name = value; }
             else if key == "greeting" { ret.greeting = value; }
         }
-        ret
+        if "" == ret.name || "" == ret.greeting {
+            return Err(HttpError::Invalid("Please tell me your name and greeting.".into()));
+        }
+        Ok(ret)
     }
 }
 
 // ----------------------------------------------------------------------------
 
-/// [`ph::Handle`]s the `/greet` URL space.
+/// [`ph::Handle`]s the `/thank` URL space.
 pub struct Thank<'a>(pub &'a mut super::Demo);
 
 impl<'a> ph::Handle for Thank<'a> {
     type Params = Params;
 
+    const METHODS: &'static [ph::Method] = &[ph::Method::Post];
+
     fn handle_get(
         &mut self,
+        _context: &ph::Context,
+        _path: &[String],
+        _params: Self::Params,
+    ) -> ph::Result {
+        Err(HttpError::MethodNotAllowed(Self::METHODS))
+    }
+
+    fn handle_post(
+        &mut self,
+        _context: &ph::Context,
         _path: &[String],
         params: Self::Params,
+        _uploads: Vec<ph::Upload>,
     ) -> ph::Result {
-        if "" != params.name && "" != params.greeting {
-            self.0.visitors.insert(params.name, params.greeting);
-            return Ok(HttpOkay::Html(Box::new(include_html!("thank.html"))));
-        }
-        Err(HttpError::Invalid)
+        self.0.visitors.insert(params.name, params.greeting);
+        Ok(HttpOkay::Html(Box::new(include_html!("thank.html"))))
     }
 }
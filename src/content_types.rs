@@ -1,157 +1,461 @@
 //! Common `Content-Type` values, adapted from
 //! [Mozilla's table](https://developer.mozilla.org/en-US/docs/Web/HTTP/Guides/MIME_types/Common_types)
 
+use std::{fmt};
+use std::borrow::{Cow};
+use std::error::{Error};
+use std::ffi::{OsStr};
+use std::fs::{File};
+use std::io::{self, Seek};
+use std::path::{Path};
+use std::str::{FromStr};
+
+use super::{multipart, remove_extension};
+
+/// A `Content-Type` value, such as `text/plain; charset=iso-8859-1`.
+///
+/// ```
+/// use petite_http::content_types::{MimeType};
+/// let mime_type: MimeType = "Multipart/Form-Data; boundary=\"a b\"".parse().unwrap();
+/// assert_eq!(mime_type.type_(), "multipart");
+/// assert_eq!(mime_type.subtype(), "form-data");
+/// assert_eq!(mime_type.param("Boundary").as_deref(), Some("a b"));
+/// assert!(mime_type.is(&petite_http::content_types::MULTIPART_FORM));
+/// assert!("text/plain; x=\u{e9}".parse::<MimeType>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MimeType(Cow<'static, str>);
+
+impl MimeType {
+    /// Wrap `s`, which must be a valid MIME type. This is not checked.
+    pub const fn from_static(s: &'static str) -> Self { Self(Cow::Borrowed(s)) }
+
+    /// The whole MIME type, including any parameters.
+    pub fn as_str(&self) -> &str { &self.0 }
+
+    /// The MIME type without its parameters, e.g. `text/plain`.
+    pub fn essence(&self) -> &str {
+        self.0.split_once(';').map_or(&*self.0, |(essence, _)| essence).trim()
+    }
+
+    /// The part of the essence before the `/`, e.g. `text`, in lower case.
+    pub fn type_(&self) -> Cow<'_, str> {
+        lower_case(self.essence().split_once('/').map_or("", |(type_, _)| type_))
+    }
+
+    /// The part of the essence after the `/`, e.g. `plain`, in lower case.
+    pub fn subtype(&self) -> Cow<'_, str> {
+        lower_case(self.essence().split_once('/').map_or("", |(_, subtype)| subtype))
+    }
+
+    /// The names (in lower case) and values of the parameters.
+    pub fn params(&self) -> Vec<(String, String)> { multipart::parameters(&self.0).1 }
+
+    /// The value of the parameter called `name`, which is case-insensitive.
+    pub fn param(&self, name: &str) -> Option<String> {
+        self.params().into_iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value)
+    }
+
+    /// Add a parameter, quoting `value` if necessary. Any existing parameter
+    /// with the same name is not removed. Fails if `name` is not a token or
+    /// `value` is not printable ASCII.
+    ///
+    /// ```
+    /// use petite_http::content_types::{TXT};
+    /// assert_eq!(TXT.with_param("x", "a b").unwrap().as_str(), "text/plain; x=\"a b\"");
+    /// assert!(TXT.with_param("x", "\u{e9}").is_err());
+    /// ```
+    pub fn with_param(self, name: &str, value: &str) -> Result<Self, InvalidMimeType> {
+        let value = if !value.is_empty() && value.chars().all(is_token) {
+            value.into()
+        } else {
+            format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+        };
+        let s = format!("{}; {}={}", self.0, name, value);
+        if name.is_empty() || !name.chars().all(is_token) || !is_printable(&value) {
+            return Err(InvalidMimeType(s));
+        }
+        Ok(Self(s.into()))
+    }
+
+    /// Returns `true` if `self` and `other` have the same essence, ignoring
+    /// case and parameters.
+    pub fn is(&self, other: &MimeType) -> bool { self.essence().eq_ignore_ascii_case(other.essence()) }
+}
+
+/// Convert `s` to lower case, without allocating if it already is.
+fn lower_case(s: &str) -> Cow<'_, str> {
+    if s.bytes().any(|b| b.is_ascii_uppercase()) { s.to_ascii_lowercase().into() } else { s.into() }
+}
+
+/// Returns `true` if `c` can be part of an unquoted type, subtype, or
+/// parameter.
+fn is_token(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)
+}
+
+/// Returns `true` if `s` is printable ASCII, which is all an HTTP header
+/// value may contain.
+fn is_printable(s: &str) -> bool {
+    s.bytes().all(|b| b'\t' == b || (b' '..=b'~').contains(&b))
+}
+
+impl fmt::Display for MimeType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { f.write_str(&self.0) }
+}
+
+/// `Error` returned when parsing a [`MimeType`] fails.
+#[derive(Debug)]
+pub struct InvalidMimeType(String);
+
+impl fmt::Display for InvalidMimeType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid MIME type {:?}", self.0)
+    }
+}
+
+impl Error for InvalidMimeType {}
+
+impl FromStr for MimeType {
+    type Err = InvalidMimeType;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid = || InvalidMimeType(s.into());
+        let (essence, params) = multipart::parameters(s);
+        let (type_, subtype) = essence.split_once('/').ok_or_else(invalid)?;
+        for token in [type_, subtype] {
+            if token.is_empty() || !token.chars().all(is_token) { return Err(invalid()); }
+        }
+        if !is_printable(s) { return Err(invalid()); }
+        for (name, _) in params {
+            if name.is_empty() || !name.chars().all(is_token) { return Err(invalid()); }
+        }
+        Ok(Self(s.to_owned().into()))
+    }
+}
+
+// ----------------------------------------------------------------------------
+
 /// AAC audio
-pub const AAC: &'static [u8] = b"audio/aac";
+pub const AAC: MimeType = MimeType::from_static("audio/aac");
 /// AbiWord document
-pub const ABW: &'static [u8] = b"application/x-abiword";
+pub const ABW: MimeType = MimeType::from_static("application/x-abiword");
 /// Animated Portable Network Graphics (APNG) image
-pub const APNG: &'static [u8] = b"image/apng";
+pub const APNG: MimeType = MimeType::from_static("image/apng");
 /// Archive document (multiple files embedded)
-pub const ARC: &'static [u8] = b"application/x-freearc";
+pub const ARC: MimeType = MimeType::from_static("application/x-freearc");
 /// AVIF image
-pub const AVIF: &'static [u8] = b"image/avif";
+pub const AVIF: MimeType = MimeType::from_static("image/avif");
 /// AVI: Audio Video Interleave
-pub const AVI: &'static [u8] = b"video/x-msvideo";
+pub const AVI: MimeType = MimeType::from_static("video/x-msvideo");
 /// Amazon Kindle eBook format
-pub const AZW: &'static [u8] = b"application/vnd.amazon.ebook";
+pub const AZW: MimeType = MimeType::from_static("application/vnd.amazon.ebook");
 /// Any kind of binary data
-pub const BIN: &'static [u8] = b"application/octet-stream";
+pub const BIN: MimeType = MimeType::from_static("application/octet-stream");
 /// Windows OS/2 Bitmap Graphics
-pub const BMP: &'static [u8] = b"image/bmp";
+pub const BMP: MimeType = MimeType::from_static("image/bmp");
 /// BZip archive
-pub const BZ: &'static [u8] = b"application/x-bzip";
+pub const BZ: MimeType = MimeType::from_static("application/x-bzip");
 /// BZip2 archive
-pub const BZ2: &'static [u8] = b"application/x-bzip2";
+pub const BZ2: MimeType = MimeType::from_static("application/x-bzip2");
 /// CD audio
-pub const CDA: &'static [u8] = b"application/x-cdf";
+pub const CDA: MimeType = MimeType::from_static("application/x-cdf");
 /// C-Shell script
-pub const CSH: &'static [u8] = b"application/x-csh";
+pub const CSH: MimeType = MimeType::from_static("application/x-csh");
 /// Cascading Style Sheets (CSS)
-pub const CSS: &'static [u8] = b"text/css";
+pub const CSS: MimeType = MimeType::from_static("text/css");
 /// Comma-separated values (CSV)
-pub const CSV: &'static [u8] = b"text/csv";
+pub const CSV: MimeType = MimeType::from_static("text/csv");
 /// Microsoft Word
-pub const DOC: &'static [u8] = b"application/msword";
+pub const DOC: MimeType = MimeType::from_static("application/msword");
 /// Microsoft Word (OpenXML)
-pub const DOCX: &'static [u8] = b"application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+pub const DOCX: MimeType = MimeType::from_static("application/vnd.openxmlformats-officedocument.wordprocessingml.document");
 /// MS Embedded OpenType fonts
-pub const EOT: &'static [u8] = b"application/vnd.ms-fontobject";
+pub const EOT: MimeType = MimeType::from_static("application/vnd.ms-fontobject");
 /// Electronic publication (EPUB)
-pub const EPUB: &'static [u8] = b"application/epub+zip";
+pub const EPUB: MimeType = MimeType::from_static("application/epub+zip");
 /// GZip Compressed Archive
-pub const GZ: &'static [u8] = b"application/gzip";
+pub const GZ: MimeType = MimeType::from_static("application/gzip");
 /// Graphics Interchange Format (GIF)
-pub const GIF: &'static [u8] = b"image/gif";
+pub const GIF: MimeType = MimeType::from_static("image/gif");
 /// HyperText Markup Language (HTML)
-pub const HTML: &'static [u8] = b"text/html";
+pub const HTML: MimeType = MimeType::from_static("text/html");
 /// Icon format
-pub const ICO: &'static [u8] = b"image/vnd.microsoft.icon";
+pub const ICO: MimeType = MimeType::from_static("image/vnd.microsoft.icon");
 /// iCalendar format
-pub const ICS: &'static [u8] = b"text/calendar";
+pub const ICS: MimeType = MimeType::from_static("text/calendar");
 /// Java Archive (JAR)
-pub const JAR: &'static [u8] = b"application/java-archive";
+pub const JAR: MimeType = MimeType::from_static("application/java-archive");
 /// JPEG images
-pub const JPEG: &'static [u8] = b"image/jpeg";
+pub const JPEG: MimeType = MimeType::from_static("image/jpeg");
 /// JavaScript
-pub const JS: &'static [u8] = b"text/javascript";
+pub const JS: MimeType = MimeType::from_static("text/javascript");
 /// JSON format
-pub const JSON: &'static [u8] = b"application/json";
+pub const JSON: MimeType = MimeType::from_static("application/json");
 /// JSON-LD format
-pub const JSONLD: &'static [u8] = b"application/ld+json";
+pub const JSONLD: MimeType = MimeType::from_static("application/ld+json");
 /// Markdown
-pub const MD: &'static [u8] = b"text/markdown";
+pub const MD: MimeType = MimeType::from_static("text/markdown");
 /// Musical Instrument Digital Interface (MIDI)
-pub const MIDI: &'static [u8] = b"audio/midi";
+pub const MIDI: MimeType = MimeType::from_static("audio/midi");
 /// JavaScript module
-pub const MJS: &'static [u8] = b"text/javascript";
+pub const MJS: MimeType = MimeType::from_static("text/javascript");
 /// MP3 audio
-pub const MP3: &'static [u8] = b"audio/mpeg";
+pub const MP3: MimeType = MimeType::from_static("audio/mpeg");
 /// MP4 video
-pub const MP4: &'static [u8] = b"video/mp4";
+pub const MP4: MimeType = MimeType::from_static("video/mp4");
 /// MPEG Video
-pub const MPEG: &'static [u8] = b"video/mpeg";
+pub const MPEG: MimeType = MimeType::from_static("video/mpeg");
 /// Apple Installer Package
-pub const MPKG: &'static [u8] = b"application/vnd.apple.installer+xml";
+pub const MPKG: MimeType = MimeType::from_static("application/vnd.apple.installer+xml");
 /// OpenDocument presentation document
-pub const ODP: &'static [u8] = b"application/vnd.oasis.opendocument.presentation";
+pub const ODP: MimeType = MimeType::from_static("application/vnd.oasis.opendocument.presentation");
 /// OpenDocument spreadsheet document
-pub const ODS: &'static [u8] = b"application/vnd.oasis.opendocument.spreadsheet";
+pub const ODS: MimeType = MimeType::from_static("application/vnd.oasis.opendocument.spreadsheet");
 /// OpenDocument text document
-pub const ODT: &'static [u8] = b"application/vnd.oasis.opendocument.text";
+pub const ODT: MimeType = MimeType::from_static("application/vnd.oasis.opendocument.text");
 /// Ogg audio
-pub const OGA: &'static [u8] = b"audio/ogg";
+pub const OGA: MimeType = MimeType::from_static("audio/ogg");
 /// Ogg video
-pub const OGV: &'static [u8] = b"video/ogg";
+pub const OGV: MimeType = MimeType::from_static("video/ogg");
 /// Ogg
-pub const OGX: &'static [u8] = b"application/ogg";
+pub const OGX: MimeType = MimeType::from_static("application/ogg");
 /// Opus audio in Ogg container
-pub const OPUS: &'static [u8] = b"audio/ogg";
+pub const OPUS: MimeType = MimeType::from_static("audio/ogg");
 /// OpenType font
-pub const OTF: &'static [u8] = b"font/otf";
+pub const OTF: MimeType = MimeType::from_static("font/otf");
 /// Portable Network Graphics
-pub const PNG: &'static [u8] = b"image/png";
+pub const PNG: MimeType = MimeType::from_static("image/png");
 /// Adobe Portable Document Format (PDF)
-pub const PDF: &'static [u8] = b"application/pdf";
+pub const PDF: MimeType = MimeType::from_static("application/pdf");
 /// Hypertext Preprocessor (Personal Home Page)
-pub const PHP: &'static [u8] = b"application/x-httpd-php";
+pub const PHP: MimeType = MimeType::from_static("application/x-httpd-php");
 /// Microsoft PowerPoint
-pub const PPT: &'static [u8] = b"application/vnd.ms-powerpoint";
+pub const PPT: MimeType = MimeType::from_static("application/vnd.ms-powerpoint");
 /// Microsoft PowerPoint (OpenXML)
-pub const PPTX: &'static [u8] = b"application/vnd.openxmlformats-officedocument.presentationml.presentation";
+pub const PPTX: MimeType = MimeType::from_static("application/vnd.openxmlformats-officedocument.presentationml.presentation");
 /// RAR archive
-pub const RAR: &'static [u8] = b"application/vnd.rar";
+pub const RAR: MimeType = MimeType::from_static("application/vnd.rar");
 /// Rich Text Format (RTF)
-pub const RTF: &'static [u8] = b"application/rtf";
+pub const RTF: MimeType = MimeType::from_static("application/rtf");
 /// Bourne shell script
-pub const SH: &'static [u8] = b"application/x-sh";
+pub const SH: MimeType = MimeType::from_static("application/x-sh");
 /// Scalable Vector Graphics (SVG)
-pub const SVG: &'static [u8] = b"image/svg+xml";
+pub const SVG: MimeType = MimeType::from_static("image/svg+xml");
 /// Tape Archive (TAR)
-pub const TAR: &'static [u8] = b"application/x-tar";
+pub const TAR: MimeType = MimeType::from_static("application/x-tar");
 /// Tagged Image File Format (TIFF)
-pub const TIFF: &'static [u8] = b"image/tiff";
+pub const TIFF: MimeType = MimeType::from_static("image/tiff");
 /// MPEG transport stream
-pub const TS: &'static [u8] = b"video/mp2t";
+pub const TS: MimeType = MimeType::from_static("video/mp2t");
 /// TrueType Font
-pub const TTF: &'static [u8] = b"font/ttf";
+pub const TTF: MimeType = MimeType::from_static("font/ttf");
 /// Text, (generally ASCII or ISO 8859-n)
-pub const TXT: &'static [u8] = b"text/plain";
+pub const TXT: MimeType = MimeType::from_static("text/plain");
 /// Microsoft Visio
-pub const VSD: &'static [u8] = b"application/vnd.visio";
+pub const VSD: MimeType = MimeType::from_static("application/vnd.visio");
 /// Waveform Audio Format
-pub const WAV: &'static [u8] = b"audio/wav";
+pub const WAV: MimeType = MimeType::from_static("audio/wav");
 /// WEBM audio
-pub const WEBA: &'static [u8] = b"audio/webm";
+pub const WEBA: MimeType = MimeType::from_static("audio/webm");
 /// WEBM video
-pub const WEBM: &'static [u8] = b"video/webm";
+pub const WEBM: MimeType = MimeType::from_static("video/webm");
 /// Web application manifest
-pub const WEBMANIFEST: &'static [u8] = b"application/manifest+json";
+pub const WEBMANIFEST: MimeType = MimeType::from_static("application/manifest+json");
 /// WEBP image
-pub const WEBP: &'static [u8] = b"image/webp";
+pub const WEBP: MimeType = MimeType::from_static("image/webp");
 /// Web Open Font Format (WOFF)
-pub const WOFF: &'static [u8] = b"font/woff";
+pub const WOFF: MimeType = MimeType::from_static("font/woff");
 /// Web Open Font Format (WOFF)
-pub const WOFF2: &'static [u8] = b"font/woff2";
+pub const WOFF2: MimeType = MimeType::from_static("font/woff2");
 /// XHTML
-pub const XHTML: &'static [u8] = b"application/xhtml+xml";
+pub const XHTML: MimeType = MimeType::from_static("application/xhtml+xml");
 /// Microsoft Excel
-pub const XLS: &'static [u8] = b"application/vnd.ms-excel";
+pub const XLS: MimeType = MimeType::from_static("application/vnd.ms-excel");
 /// Microsoft Excel (OpenXML)
-pub const XLSX: &'static [u8] = b"application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+pub const XLSX: MimeType = MimeType::from_static("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet");
 /// XML
-pub const XML: &'static [u8] = b"application/xml";
+pub const XML: MimeType = MimeType::from_static("application/xml");
 /// XUL
-pub const XUL: &'static [u8] = b"application/vnd.mozilla.xul+xml";
+pub const XUL: MimeType = MimeType::from_static("application/vnd.mozilla.xul+xml");
 /// ZIP archive
-pub const ZIP: &'static [u8] = b"application/zip";
+pub const ZIP: MimeType = MimeType::from_static("application/zip");
 /// 3GPP audio container
-pub const _3GP_AUDIO: &'static [u8] = b"audio/3gpp";
+pub const _3GP_AUDIO: MimeType = MimeType::from_static("audio/3gpp");
 /// 3GPP video container
-pub const _3GP_VIDEO: &'static [u8] = b"video/3gpp";
+pub const _3GP_VIDEO: MimeType = MimeType::from_static("video/3gpp");
 /// 3GPP2 audio container
-pub const _3G2_AUDIO: &'static [u8] = b"audio/3gpp2";
+pub const _3G2_AUDIO: MimeType = MimeType::from_static("audio/3gpp2");
 /// 3GPP2 video container
-pub const _3G2_VIDEO: &'static [u8] = b"video/3gpp2";
+pub const _3G2_VIDEO: MimeType = MimeType::from_static("video/3gpp2");
 /// 7-zip archive
-pub const _7Z: &'static [u8] = b"application/x-7z-compressed";
+pub const _7Z: MimeType = MimeType::from_static("application/x-7z-compressed");
+
+// ----------------------------------------------------------------------------
+
+/// HTML form data
+pub const FORM: MimeType = MimeType::from_static("application/x-www-form-urlencoded");
+/// HTML form data, possibly including files
+pub const MULTIPART_FORM: MimeType = MimeType::from_static("multipart/form-data");
+
+// ----------------------------------------------------------------------------
+
+/// File extensions and the corresponding `Content-Type`s.
+const EXTENSIONS: &'static [(&'static str, MimeType)] = &[
+    ("aac", AAC),
+    ("abw", ABW),
+    ("apng", APNG),
+    ("arc", ARC),
+    ("avif", AVIF),
+    ("avi", AVI),
+    ("azw", AZW),
+    ("bin", BIN),
+    ("bmp", BMP),
+    ("bz", BZ),
+    ("bz2", BZ2),
+    ("cda", CDA),
+    ("csh", CSH),
+    ("css", CSS),
+    ("csv", CSV),
+    ("doc", DOC),
+    ("docx", DOCX),
+    ("eot", EOT),
+    ("epub", EPUB),
+    ("gz", GZ),
+    ("gif", GIF),
+    ("htm", HTML),
+    ("html", HTML),
+    ("ico", ICO),
+    ("ics", ICS),
+    ("jar", JAR),
+    ("jpeg", JPEG),
+    ("jpg", JPEG),
+    ("js", JS),
+    ("json", JSON),
+    ("jsonld", JSONLD),
+    ("md", MD),
+    ("mid", MIDI),
+    ("midi", MIDI),
+    ("mjs", MJS),
+    ("mp3", MP3),
+    ("mp4", MP4),
+    ("mpeg", MPEG),
+    ("mpkg", MPKG),
+    ("odp", ODP),
+    ("ods", ODS),
+    ("odt", ODT),
+    ("oga", OGA),
+    ("ogv", OGV),
+    ("ogx", OGX),
+    ("opus", OPUS),
+    ("otf", OTF),
+    ("png", PNG),
+    ("pdf", PDF),
+    ("php", PHP),
+    ("ppt", PPT),
+    ("pptx", PPTX),
+    ("rar", RAR),
+    ("rtf", RTF),
+    ("sh", SH),
+    ("svg", SVG),
+    ("tar", TAR),
+    ("tif", TIFF),
+    ("tiff", TIFF),
+    ("ts", TS),
+    ("ttf", TTF),
+    ("txt", TXT),
+    ("vsd", VSD),
+    ("wav", WAV),
+    ("weba", WEBA),
+    ("webm", WEBM),
+    ("webmanifest", WEBMANIFEST),
+    ("webp", WEBP),
+    ("woff", WOFF),
+    ("woff2", WOFF2),
+    ("xhtml", XHTML),
+    ("xls", XLS),
+    ("xlsx", XLSX),
+    ("xml", XML),
+    ("xul", XUL),
+    ("zip", ZIP),
+    ("3gp", _3GP_VIDEO),
+    ("3g2", _3G2_VIDEO),
+    ("7z", _7Z),
+];
+
+/// Guess the `Content-Type` of a file from the extension of `filename`,
+/// which is case-insensitive.
+///
+/// ```
+/// use petite_http::content_types::{self, from_filename};
+/// assert_eq!(from_filename("style.CSS"), Some(content_types::CSS));
+/// assert_eq!(from_filename("README"), None);
+/// ```
+pub fn from_filename(filename: &str) -> Option<MimeType> {
+    EXTENSIONS.iter().find(
+        |(extension, _)| remove_extension(filename, extension).is_some()
+    ).map(|(_, content_type)| content_type.clone())
+}
+
+/// Guess the `Content-Type` of a `File` from its contents, and rewind it.
+///
+/// This is slower and less reliable than [`from_filename()`]; in particular
+/// it can't recognise text formats such as CSS.
+pub fn sniff(file: &mut File) -> io::Result<MimeType> {
+    let mime_type = tika_magic::from_file(file).ok_or_else(|| io::Error::other("error getting MIME type"))?;
+    file.rewind()?;
+    Ok(MimeType::from_static(mime_type))
+}
+
+/// Guess the `Content-Type` of the file at `path`, which is open as `file`.
+///
+/// Uses [`from_filename()`] if the extension is recognised, otherwise
+/// [`sniff()`].
+pub fn guess(path: &Path, file: &mut File) -> io::Result<MimeType> {
+    let filename = path.file_name().and_then(OsStr::to_str);
+    filename.and_then(from_filename).map_or_else(|| sniff(file), Ok)
+}
+
+/// Choose the best of `offers` for a client that sent `accept`, the value of
+/// an HTTP `Accept` header such as `text/html, application/*;q=0.5`.
+///
+/// Each offer gets the quality value of the most specific media range that
+/// matches it. Parameters of the media ranges other than `q` are ignored.
+/// Returns the first offer with the highest quality value, or `None` if no
+/// offer is acceptable.
+///
+/// ```
+/// use petite_http::content_types::{negotiate, CSV, HTML, JSON};
+/// let accept = "text/*;q=0.5, application/json, text/csv;q=0";
+/// assert_eq!(negotiate(accept, &[HTML, JSON]), Some(JSON));
+/// assert_eq!(negotiate(accept, &[HTML, CSV]), Some(HTML));
+/// assert_eq!(negotiate(accept, &[CSV]), None);
+/// ```
+pub fn negotiate(accept: &str, offers: &[MimeType]) -> Option<MimeType> {
+    let ranges: Vec<(MimeType, f32)> = accept.split(',').filter(|range| !range.trim().is_empty()).filter_map(|range| {
+        let range: MimeType = range.parse().ok()?;
+        let q = match range.param("q") {
+            Some(q) => q.parse().ok().filter(|q| (0.0 ..= 1.0).contains(q))?,
+            None => 1.0,
+        };
+        Some((range, q))
+    }).collect();
+    let mut best: Option<(&MimeType, f32)> = None;
+    for offer in offers {
+        let q = ranges.iter().filter_map(
+            |(range, q)| specificity(range, offer).map(|specificity| (specificity, *q))
+        ).max_by_key(|&(specificity, _)| specificity).map_or(0.0, |(_, q)| q);
+        if q > 0.0 && best.is_none_or(|(_, best_q)| q > best_q) { best = Some((offer, q)); }
+    }
+    best.map(|(offer, _)| offer.clone())
+}
+
+/// If the media range `range` matches `offer`, returns how specific it is.
+fn specificity(range: &MimeType, offer: &MimeType) -> Option<u8> {
+    if range.is(offer) { return Some(2); }
+    if "*" != range.subtype() { return None; }
+    if "*" == range.type_() { return Some(0); }
+    if range.type_() == offer.type_() { return Some(1); }
+    None
+}
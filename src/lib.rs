@@ -3,22 +3,41 @@ use std::error::{Error};
 use std::ffi::{OsStr, OsString};
 
 pub mod content_types;
+pub use content_types::{MimeType};
 
 pub mod html;
 
+mod multipart;
+pub use multipart::{Upload};
+
 mod server;
-pub use server::{HttpOkay, HttpError, Result, Callback, Route, Handle, start};
+pub use server::{HttpOkay, HttpError, Result, Context, Body, Callback, Route, SharedRoute, Handle, TryFromParams};
+pub use server::{start, start_pool, spawn, spawn_pool, ServerBuilder, Server, ServerHandle, StartError};
+
+#[cfg(feature = "serde")]
+mod query;
+#[cfg(feature = "serde")]
+pub use query::{Query};
+
+mod static_dir;
+pub use static_dir::{StaticDir};
+
+pub use tiny_http::{Method};
+
+pub mod testing;
 
 // ----------------------------------------------------------------------------
 
 /// Given `"foo.BAR"` and `"bar"` returns `Some("foo")`.
 pub fn remove_extension<'a>(filename: &'a str, extension: &str) -> Option<&'a str> {
-    if let Some(index) = filename.len().checked_sub(".".len() + extension.len()) {
-        if let Some((ret, tail)) = filename.split_at_checked(index) {
-            let mut tail = tail.chars();
-            if let Some('.') = tail.next() {
-                if extension.eq_ignore_ascii_case(tail.as_str()) { return Some(ret); }
-            }
+    if let Some(index) = filename.len().checked_sub(".".len() + extension.len())
+        && let Some((ret, tail)) = filename.split_at_checked(index)
+    {
+        let mut tail = tail.chars();
+        if let Some('.') = tail.next()
+            && extension.eq_ignore_ascii_case(tail.as_str())
+        {
+            return Some(ret);
         }
     }
     None
@@ -48,9 +67,9 @@ impl Error for DubiousFilename {}
 pub fn validate_name(s: &OsStr) -> std::result::Result<&str, DubiousFilename> {
     for b in s.as_encoded_bytes() {
         match b {
-            b'0' .. b'9' => {},
-            b'A' .. b'Z' => {},
-            b'a' .. b'z' => {},
+            b'0' ..= b'9' => {},
+            b'A' ..= b'Z' => {},
+            b'a' ..= b'z' => {},
             b'_' | b'.' | b'-' => {}
             _ => { return Err(DubiousFilename(s.to_owned())); }
         }
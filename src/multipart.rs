@@ -0,0 +1,223 @@
+//! Parse `multipart/form-data` request bodies.
+
+use std::fs::{File};
+use std::io::{self, Read, Seek, Write};
+
+use super::{validate_name, DubiousFilename, HttpError, MimeType};
+
+/// The maximum length of the headers of one part.
+const MAX_HEADERS_LENGTH: usize = 8 * 1024;
+
+/// Form field names and values.
+type Fields = Vec<(String, String)>;
+
+/// A file uploaded in a `multipart/form-data` request body.
+#[derive(Debug)]
+pub struct Upload {
+    /// The name of the form field.
+    pub name: String,
+
+    /// The filename supplied by the client, if it passes [`validate_name()`].
+    pub filename: std::result::Result<String, DubiousFilename>,
+
+    /// The `Content-Type` declared by the client, if it is present and valid.
+    pub content_type: Option<MimeType>,
+
+    /// The contents of the file, which is stored in an anonymous temporary
+    /// file positioned at the start.
+    pub file: File,
+}
+
+// ----------------------------------------------------------------------------
+
+/// Split a header value such as `form-data; name="foo"; filename="bar.txt"`
+/// into its leading value and its parameters.
+///
+/// Parameter names are converted to lower case. Quoted values are unquoted.
+pub(crate) fn parameters(header: &str) -> (&str, Vec<(String, String)>) {
+    let (first, mut rest) = header.split_once(';').unwrap_or((header, ""));
+    let mut ret = Vec::new();
+    while let Some((name, tail)) = rest.split_once('=') {
+        let name = name.trim().to_ascii_lowercase();
+        let tail = tail.trim_start();
+        let mut value = String::new();
+        if let Some(quoted) = tail.strip_prefix('"') {
+            let mut chars = quoted.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => { break; },
+                    '\\' => { if let Some(c) = chars.next() { value.push(c); } },
+                    c => { value.push(c); },
+                }
+            }
+            rest = chars.as_str().split_once(';').map_or("", |(_, rest)| rest);
+        } else {
+            let (v, tail) = tail.split_once(';').unwrap_or((tail, ""));
+            value.push_str(v.trim());
+            rest = tail;
+        }
+        ret.push((name, value));
+    }
+    (first.trim(), ret)
+}
+
+/// Returns the index of the first occurrence of `needle` in `haystack`.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+/// A streaming parser for a `multipart/form-data` body.
+struct Parser<'a> {
+    body: &'a mut dyn Read,
+
+    /// Data that has been read from `body` but not yet consumed.
+    buffer: Vec<u8>,
+
+    /// `"\r\n--"` followed by the boundary.
+    delimiter: Vec<u8>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(body: &'a mut dyn Read, boundary: &str) -> Self {
+        // The first delimiter is not preceded by a line break, so pretend it is.
+        let buffer = b"\r\n".to_vec();
+        let delimiter = [b"\r\n--", boundary.as_bytes()].concat();
+        Self {body, buffer, delimiter}
+    }
+
+    /// Read more data into `buffer`. Fails at the end of `body`.
+    fn fill(&mut self) -> std::result::Result<(), HttpError> {
+        let mut chunk = [0; 8 * 1024];
+        let n = self.body.read(&mut chunk)?;
+        if n == 0 { return Err(HttpError::Invalid("The multipart body ends unexpectedly".into())); }
+        self.buffer.extend_from_slice(&chunk[..n]);
+        Ok(())
+    }
+
+    /// Ensure that `buffer` contains at least `n` bytes.
+    fn fill_to(&mut self, n: usize) -> std::result::Result<(), HttpError> {
+        while self.buffer.len() < n { self.fill()?; }
+        Ok(())
+    }
+
+    /// Copy data to `sink` up to the next delimiter, and consume the delimiter.
+    fn copy_to_delimiter(&mut self, sink: &mut dyn Write) -> std::result::Result<(), HttpError> {
+        loop {
+            if let Some(index) = find(&self.buffer, &self.delimiter) {
+                sink.write_all(&self.buffer[..index])?;
+                self.buffer.drain(..index + self.delimiter.len());
+                return Ok(());
+            }
+            // Keep anything that might be the start of a delimiter.
+            let keep = self.delimiter.len() - 1;
+            if self.buffer.len() > keep {
+                let index = self.buffer.len() - keep;
+                sink.write_all(&self.buffer[..index])?;
+                self.buffer.drain(..index);
+            }
+            self.fill()?;
+        }
+    }
+
+    /// Having consumed a delimiter, find out whether another part follows.
+    fn next_part(&mut self) -> std::result::Result<bool, HttpError> {
+        self.fill_to(2)?;
+        if self.buffer.starts_with(b"--") { return Ok(false); }
+        // Skip transport padding and the line break.
+        loop {
+            if let Some(index) = find(&self.buffer, b"\r\n") {
+                self.buffer.drain(..index + 2);
+                return Ok(true);
+            }
+            if self.buffer.len() > MAX_HEADERS_LENGTH {
+                return Err(HttpError::Invalid("Invalid multipart delimiter".into()));
+            }
+            self.fill()?;
+        }
+    }
+
+    /// Read the headers of a part and return their names (in lower case)
+    /// and values.
+    fn headers(&mut self) -> std::result::Result<Vec<(String, String)>, HttpError> {
+        let end = loop {
+            if self.buffer.starts_with(b"\r\n") { break 0; }
+            if let Some(index) = find(&self.buffer, b"\r\n\r\n") { break index + 2; }
+            if self.buffer.len() > MAX_HEADERS_LENGTH { return Err(HttpError::Invalid("Multipart headers are too long".into())); }
+            self.fill()?;
+        };
+        let headers = std::str::from_utf8(&self.buffer[..end]).map_err(
+            |_| HttpError::Invalid("Multipart headers are not UTF-8".into())
+        )?;
+        let headers = headers.split_terminator("\r\n").map(|line| {
+            let (name, value) = line.split_once(':').ok_or_else(
+                || HttpError::Invalid(format!("Invalid multipart header {:?}", line))
+            )?;
+            Ok((name.trim().to_ascii_lowercase(), value.trim().to_owned()))
+        }).collect();
+        self.buffer.drain(..end + 2);
+        headers
+    }
+}
+
+/// Parse a `multipart/form-data` body, returning the form fields and the
+/// uploaded files.
+///
+/// - body - The request body.
+/// - content_type - The `Content-Type` of the request, which specifies the
+///   boundary.
+/// - max_uploads - The maximum number of files. More fail with
+///   [`HttpError::PayloadTooLarge`].
+pub(crate) fn parse(
+    body: &mut dyn Read,
+    content_type: &MimeType,
+    max_uploads: usize,
+) -> std::result::Result<(Fields, Vec<Upload>), HttpError> {
+    let boundary = content_type.param("boundary").ok_or_else(
+        || HttpError::Invalid("The multipart boundary is missing".into())
+    )?;
+    let mut parser = Parser::new(body, &boundary);
+    parser.copy_to_delimiter(&mut io::sink())?;
+    let mut fields = Vec::new();
+    let mut uploads = Vec::new();
+    while parser.next_part()? {
+        let mut disposition = None;
+        let mut content_type = None;
+        for (key, value) in parser.headers()? {
+            if key == "content-disposition" { disposition = Some(value); }
+            else if key == "content-type" { content_type = value.parse().ok(); }
+        }
+        let disposition = disposition.ok_or_else(|| HttpError::Invalid("Content-Disposition is missing".into()))?;
+        let (kind, params) = parameters(&disposition);
+        if !kind.eq_ignore_ascii_case("form-data") {
+            return Err(HttpError::Invalid(format!("Unexpected Content-Disposition {:?}", kind)));
+        }
+        let mut name = None;
+        let mut filename = None;
+        for (key, value) in params {
+            if key == "name" { name = Some(value); }
+            else if key == "filename" { filename = Some(value); }
+        }
+        let name = name.ok_or_else(|| HttpError::Invalid("A form field has no name".into()))?;
+        if let Some(filename) = filename {
+            if filename == "" {
+                // A file input that was left empty.
+                parser.copy_to_delimiter(&mut io::sink())?;
+                continue;
+            }
+            if uploads.len() >= max_uploads { return Err(HttpError::PayloadTooLarge); }
+            let mut file = tempfile::tempfile()?;
+            parser.copy_to_delimiter(&mut file)?;
+            file.rewind()?;
+            let filename = validate_name(filename.as_ref()).map(str::to_owned);
+            uploads.push(Upload {name, filename, content_type, file});
+        } else {
+            let mut value = Vec::new();
+            parser.copy_to_delimiter(&mut value)?;
+            let value = String::from_utf8(value).map_err(
+                |_| HttpError::Invalid(format!("Form field {:?} is not UTF-8", name))
+            )?;
+            fields.push((name, value));
+        }
+    }
+    Ok((fields, uploads))
+}
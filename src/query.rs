@@ -0,0 +1,241 @@
+//! Deserialize URL request parameters and form fields using serde.
+
+use std::{fmt};
+use std::collections::{HashMap};
+use std::error::{Error};
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+
+use super::{HttpError, TryFromParams};
+
+/// A [`Handle::Params`](crate::Handle::Params) that deserializes the
+/// parameters into `T`. Requires the `serde` feature.
+///
+/// Each field of `T` is read from the parameters with the same name. Fields
+/// can be strings, numbers, `bool`s (`true`, `on` or `1`), or enums with unit
+/// variants. `Option` fields may be absent or empty. `Vec` fields collect
+/// repeated parameters, and need `#[serde(default)]` to be absent. Unknown
+/// parameters are ignored unless `T` has `#[serde(deny_unknown_fields)]`.
+///
+/// If the parameters do not match `T` the request is rejected with
+/// [`HttpError::Invalid`], explaining why.
+///
+/// ```
+/// use petite_http::{Query, TryFromParams};
+///
+/// #[derive(serde::Deserialize)]
+/// struct Search {
+///     q: String,
+///     page: Option<u32>,
+///     #[serde(default)]
+///     tag: Vec<String>,
+/// }
+///
+/// let params = [("q", "cats"), ("tag", "cute"), ("tag", "fluffy")];
+/// let Query(search) = Query::<Search>::try_from_params(params.map(|(k, v)| (k.into(), v.into()))).unwrap();
+/// assert_eq!((search.q.as_str(), search.page, search.tag.len()), ("cats", None, 2));
+///
+/// assert!(Query::<Search>::try_from_params([("page".into(), "two".into())]).is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Query<T>(pub T);
+
+impl<T: DeserializeOwned> TryFromParams for Query<T> {
+    fn try_from_params(params: impl IntoIterator<Item = (String, String)>) -> std::result::Result<Self, HttpError> {
+        from_params(params).map(Self).map_err(|e| HttpError::Invalid(e.0))
+    }
+}
+
+/// Deserialize `params` into a `T`.
+fn from_params<T: DeserializeOwned>(
+    params: impl IntoIterator<Item = (String, String)>,
+) -> std::result::Result<T, QueryError> {
+    // Group the values of repeated keys, keeping the keys in order.
+    let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+    let mut indices: HashMap<String, usize> = HashMap::new();
+    for (key, value) in params {
+        match indices.get(&key) {
+            Some(&i) => { groups[i].1.push(value); },
+            None => {
+                indices.insert(key.clone(), groups.len());
+                groups.push((key, vec![value]));
+            },
+        }
+    }
+    T::deserialize(ParamsDeserializer {groups: groups.into_iter(), current: None})
+}
+
+// ----------------------------------------------------------------------------
+
+/// The `Error` type of the deserializers in this module.
+#[derive(Debug)]
+struct QueryError(String);
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { f.write_str(&self.0) }
+}
+
+impl Error for QueryError {}
+
+impl de::Error for QueryError {
+    fn custom<M: fmt::Display>(msg: M) -> Self { Self(msg.to_string()) }
+
+    fn missing_field(field: &'static str) -> Self { Self(format!("Missing parameter {:?}", field)) }
+
+    fn unknown_field(field: &str, _expected: &'static [&'static str]) -> Self {
+        Self(format!("Unknown parameter {:?}", field))
+    }
+}
+
+/// Deserializes all the parameters, as a map or struct.
+struct ParamsDeserializer {
+    groups: std::vec::IntoIter<(String, Vec<String>)>,
+
+    /// The key and values returned by `next_key_seed()`.
+    current: Option<(String, Vec<String>)>,
+}
+
+impl<'de> Deserializer<'de> for ParamsDeserializer {
+    type Error = QueryError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, QueryError> {
+        visitor.visit_map(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de> MapAccess<'de> for ParamsDeserializer {
+    type Error = QueryError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> std::result::Result<Option<K::Value>, QueryError> {
+        let Some((key, values)) = self.groups.next() else { return Ok(None); };
+        let ret = seed.deserialize(key.as_str().into_deserializer())?;
+        self.current = Some((key, values));
+        Ok(Some(ret))
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> std::result::Result<V::Value, QueryError> {
+        let (key, values) = self.current.take().expect("next_value_seed() called before next_key_seed()");
+        seed.deserialize(ValuesDeserializer(values)).map_err(
+            |e| QueryError(format!("Parameter {:?}: {}", key, e))
+        )
+    }
+}
+
+/// Deserializes all the values of one key.
+struct ValuesDeserializer(Vec<String>);
+
+impl ValuesDeserializer {
+    /// Returns the only value.
+    fn value(mut self) -> std::result::Result<String, QueryError> {
+        if self.0.len() > 1 { return Err(QueryError("expected one value".into())); }
+        Ok(self.0.pop().unwrap_or_default())
+    }
+
+    /// Parse the only value.
+    fn parse<T: std::str::FromStr>(self, expected: &str) -> std::result::Result<T, QueryError> {
+        let value = self.value()?;
+        value.trim().parse().map_err(|_| QueryError(format!("expected {}, found {:?}", expected, value)))
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident $visit:ident $expected:literal,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, QueryError> {
+                visitor.$visit(self.parse($expected)?)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for ValuesDeserializer {
+    type Error = QueryError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, QueryError> {
+        if self.0.len() > 1 { return self.deserialize_seq(visitor); }
+        visitor.visit_string(self.value()?)
+    }
+
+    deserialize_parsed! {
+        deserialize_i8 visit_i8 "an integer",
+        deserialize_i16 visit_i16 "an integer",
+        deserialize_i32 visit_i32 "an integer",
+        deserialize_i64 visit_i64 "an integer",
+        deserialize_i128 visit_i128 "an integer",
+        deserialize_u8 visit_u8 "a non-negative integer",
+        deserialize_u16 visit_u16 "a non-negative integer",
+        deserialize_u32 visit_u32 "a non-negative integer",
+        deserialize_u64 visit_u64 "a non-negative integer",
+        deserialize_u128 visit_u128 "a non-negative integer",
+        deserialize_f32 visit_f32 "a number",
+        deserialize_f64 visit_f64 "a number",
+        deserialize_char visit_char "a single character",
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, QueryError> {
+        let value = self.value()?;
+        match value.trim() {
+            "true" | "on" | "1" => visitor.visit_bool(true),
+            "false" | "off" | "0" => visitor.visit_bool(false),
+            _ => Err(QueryError(format!("expected true or false, found {:?}", value))),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, QueryError> {
+        visitor.visit_string(self.value()?)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, QueryError> {
+        visitor.visit_string(self.value()?)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, QueryError> {
+        if let [value] = self.0.as_slice() && "" == value.trim() { return visitor.visit_none(); }
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> std::result::Result<V::Value, QueryError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, QueryError> {
+        visitor.visit_seq(ValuesSeq(self.0.into_iter()))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, QueryError> {
+        visitor.visit_enum(self.value()?.into_deserializer())
+    }
+
+    serde::forward_to_deserialize_any! {
+        bytes byte_buf unit unit_struct tuple tuple_struct map
+        struct identifier ignored_any
+    }
+}
+
+/// The values of one key, as a sequence.
+struct ValuesSeq(std::vec::IntoIter<String>);
+
+impl<'de> SeqAccess<'de> for ValuesSeq {
+    type Error = QueryError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> std::result::Result<Option<T::Value>, QueryError> {
+        self.0.next().map(|value| seed.deserialize(ValuesDeserializer(vec![value]))).transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> { Some(self.0.len()) }
+}
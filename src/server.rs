@@ -1,13 +1,20 @@
 use std::{fmt};
 use std::error::{Error};
 use std::fs::{File};
-use std::io::{Seek};
+use std::collections::{VecDeque};
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::path::{Path};
+use std::net::{SocketAddr};
+use std::sync::{mpsc, Arc, Mutex, Once, PoisonError, Weak};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use tiny_http::{Method, Request, Response, Header};
+use tiny_http::{Method, Request, Response, ResponseBox, Header, StatusCode};
 
 use url::{Url};
 
-use super::{content_types, html};
+use super::{content_types, html, multipart, MimeType, Upload};
 
 /// A normal HTTP response.
 #[derive(Debug)]
@@ -15,28 +22,152 @@ pub enum HttpOkay {
     /// A static file.
     ///
     /// If the content type is `None`, it will be inferred from the file
-    /// contents.
-    File {file: File, content_type: Option<&'static [u8]>},
+    /// contents. [`HttpOkay::from_path()`] infers it from the filename.
+    File {file: File, content_type: Option<MimeType>},
 
-    /// Dynamic HTML.
+    /// Dynamic HTML, sent with `Content-Type: text/html; charset=utf-8`.
     Html(Box<dyn html::Escape>),
 
-    /// Dynamic character data.
-    Chars {data: String, content_type: &'static [u8]},
+    /// Dynamic character data. If `content_type` is textual, `charset=utf-8`
+    /// is added to it unless it already has a `charset`.
+    Chars {data: String, content_type: MimeType},
 
     /// Dynamic binary data.
-    Bytes {data: Vec<u8>, content_type: &'static [u8]},
+    Bytes {data: Vec<u8>, content_type: MimeType},
 
     /// Temporary redirect. The client should immediately request the given
     /// URL, which is relative to the `base_url` of the [`Handle`].
+    ///
+    /// Use [`HttpOkay::with_status()`] to select a different kind of redirect.
     Redirect(String),
+
+    /// No content. The status code is 204 (No Content) unless overridden.
+    Empty,
+
+    /// Another response with a different status code and/or extra headers.
+    ///
+    /// It is usually easiest to construct this using
+    /// [`HttpOkay::with_status()`] and [`HttpOkay::with_header()`].
+    Custom {
+        /// The response to modify.
+        body: Box<HttpOkay>,
+
+        /// If specified, overrides the status code of `body`.
+        status: Option<u16>,
+
+        /// HTTP header names and values to add to the response.
+        headers: Vec<(&'static str, String)>,
+    },
+}
+
+impl HttpOkay {
+    /// Open the file at `path`, as an [`HttpOkay::File`] whose content type
+    /// is inferred from the filename if possible, or else from the file
+    /// contents. See [`content_types::guess()`].
+    pub fn from_path(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        let filename = path.file_name().and_then(|name| name.to_str());
+        let content_type = filename.and_then(content_types::from_filename);
+        Ok(Self::File {file: File::open(path)?, content_type})
+    }
+
+    /// Serialize `value` as JSON, with `Content-Type: application/json`.
+    /// Requires the `json` feature.
+    #[cfg(feature = "json")]
+    pub fn json<T: serde::Serialize + ?Sized>(value: &T) -> std::result::Result<Self, HttpError> {
+        let data = serde_json::to_vec(value).map_err(HttpError::new)?;
+        Ok(Self::Bytes {data, content_type: content_types::JSON})
+    }
+
+    /// The response to an `OPTIONS` request for a resource that allows
+    /// `methods`. See [`Context::check_method()`].
+    pub fn options(methods: &[Method]) -> Self {
+        Self::Empty.with_header("Allow", allow(methods))
+    }
+
+    /// Override the status code of `self`, e.g. with 201 (Created).
+    pub fn with_status(self, status: u16) -> Self {
+        let (body, _, headers) = self.into_custom();
+        Self::Custom {body, status: Some(status), headers}
+    }
+
+    /// Add an HTTP header to `self`, e.g. `Cache-Control`.
+    ///
+    /// This overrides `Content-Type`. The server manages `Content-Length`,
+    /// `Connection`, `Transfer-Encoding`, `Trailer` and `Upgrade` itself, so
+    /// adding them results in a server error.
+    pub fn with_header(self, key: &'static str, value: impl Into<String>) -> Self {
+        let (body, status, mut headers) = self.into_custom();
+        headers.push((key, value.into()));
+        Self::Custom {body, status, headers}
+    }
+
+    /// Add a strong `ETag` header to `self`, so that the server can answer
+    /// `If-None-Match` requests with `304 Not Modified`. `tag` must change
+    /// whenever the response does, and must not contain `"`.
+    ///
+    /// Files are given a weak `ETag` automatically.
+    pub fn with_etag(self, tag: &str) -> Self {
+        self.with_header("ETag", format!("\"{}\"", tag))
+    }
+
+    /// Returns the fields of an [`HttpOkay::Custom`] equivalent to `self`.
+    fn into_custom(self) -> (Box<Self>, Option<u16>, Vec<(&'static str, String)>) {
+        match self {
+            Self::Custom {body, status, headers} => (body, status, headers),
+            body => (Box::new(body), None, Vec::new()),
+        }
+    }
 }
 
 /// An erroneous HTTP response.
 #[derive(Debug)]
 pub enum HttpError {
-    Invalid,
+    /// 400 Bad Request. The message explains what is wrong with the request,
+    /// and is sent to the client.
+    Invalid(String),
+
+    /// 401 Unauthorized. The client is not authenticated.
+    Unauthorized,
+
+    /// 403 Forbidden. The client is authenticated but lacks permission.
+    Forbidden,
+
+    /// 404 Not Found.
     NotFound,
+
+    /// 405 Method Not Allowed. Lists the methods that are allowed, apart
+    /// from `OPTIONS`.
+    MethodNotAllowed(&'static [Method]),
+
+    /// 406 Not Acceptable. None of the available content types is acceptable
+    /// to the client. See [`Context::negotiate()`].
+    NotAcceptable,
+
+    /// 409 Conflict, e.g. with the current state of a resource.
+    Conflict,
+
+    /// 410 Gone. The resource has been deliberately removed.
+    Gone,
+
+    /// 413 Payload Too Large. The request [`Body`] exceeds the maximum length.
+    PayloadTooLarge,
+
+    /// 415 Unsupported Media Type. The request body is of the wrong type.
+    UnsupportedMediaType,
+
+    /// 416 Range Not Satisfiable. The argument is the length of the
+    /// resource in bytes.
+    RangeNotSatisfiable(u64),
+
+    /// 429 Too Many Requests.
+    TooManyRequests,
+
+    /// 503 Service Unavailable. Optionally suggests when to try again.
+    ServiceUnavailable(Option<Duration>),
+
+    /// 500 Internal Server Error. The error is logged but not sent to the
+    /// client.
     Error(Box<dyn Error>),
 }
 
@@ -60,26 +191,108 @@ macro_rules! impl_httperror_from {
     };
 }
 
-impl_httperror_from!(std::io::Error);
 impl_httperror_from!(url::ParseError);
 impl_httperror_from!(crate::DubiousFilename);
 
-/// The return type of [`Handle::handle_get()`].
+impl From<std::io::Error> for HttpError {
+    fn from(e: std::io::Error) -> Self {
+        if e.get_ref().is_some_and(|e| e.is::<TooLong>()) { return Self::PayloadTooLarge; }
+        Self::new(e)
+    }
+}
+
+/// The return type of [`Handle::handle_get()`] and [`Handle::handle_post()`].
 pub type Result = std::result::Result<HttpOkay, HttpError>;
 
 // ----------------------------------------------------------------------------
 
+/// Information about an HTTP request, other than its body.
+#[derive(Debug, Clone)]
+pub struct Context {
+    method: Method,
+    url: Url,
+    base_url: Url,
+    headers: Vec<Header>,
+    remote_addr: Option<SocketAddr>,
+}
+
+impl Context {
+    /// The HTTP method, e.g. `GET`.
+    pub fn method(&self) -> &Method { &self.method }
+
+    /// The requested URL, including the query string, as seen by the client.
+    pub fn url(&self) -> &Url { &self.url }
+
+    /// The publicly visible URL of the web server.
+    pub fn base_url(&self) -> &Url { &self.base_url }
+
+    /// The value of the first HTTP header called `name` (ignoring case), if any.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers().find_map(
+            |(key, value)| if key.eq_ignore_ascii_case(name) { Some(value) } else { None }
+        )
+    }
+
+    /// The `Content-Type` of the request body, if it is present and valid.
+    pub fn content_type(&self) -> Option<MimeType> {
+        self.header("Content-Type")?.parse().ok()
+    }
+
+    /// Choose the best of `offers` according to the `Accept` header of the
+    /// request. See [`content_types::negotiate()`].
+    ///
+    /// If there is no `Accept` header, returns the first offer. Fails with
+    /// [`HttpError::NotAcceptable`] if no offer is acceptable.
+    pub fn negotiate(&self, offers: &[MimeType]) -> std::result::Result<MimeType, HttpError> {
+        let best = match self.header("Accept") {
+            Some(accept) => content_types::negotiate(accept, offers),
+            None => offers.first().cloned(),
+        };
+        best.ok_or(HttpError::NotAcceptable)
+    }
+
+    /// The names and values of all the HTTP headers, in order.
+    pub fn headers(&self) -> impl Iterator<Item=(&str, &str)> {
+        self.headers.iter().map(|h| (h.field.as_str().as_str(), h.value.as_str()))
+    }
+
+    /// The address of the client, if known. This may be a proxy.
+    pub fn remote_addr(&self) -> Option<SocketAddr> { self.remote_addr }
+
+    /// Check the HTTP method of a resource that allows `methods`.
+    ///
+    /// Returns the response to an `OPTIONS` request, or `None` if the method
+    /// is allowed. Fails with [`HttpError::MethodNotAllowed`] otherwise. See
+    /// [`Route::route()`].
+    pub fn check_method(&self, methods: &'static [Method]) -> std::result::Result<Option<HttpOkay>, HttpError> {
+        if Method::Options == self.method { return Ok(Some(HttpOkay::options(methods))); }
+        if !methods.contains(&self.method) { return Err(HttpError::MethodNotAllowed(methods)); }
+        Ok(None)
+    }
+}
+
+// ----------------------------------------------------------------------------
+
 /// Implement this to write your web application.
 pub trait Handle {
     /// Represents the URL request parameters that are recognised by this
     /// Handle.
     ///
     /// A [`std::collections::HashMap`] is a possible choice, or you can
-    /// provide something with more type-checking.
-    type Params: FromIterator<(String, String)>;
+    /// provide something with more type-checking. See [`TryFromParams`].
+    type Params: TryFromParams;
+
+    /// The HTTP methods that this Handle implements. Requests with other
+    /// methods are rejected with [`HttpError::MethodNotAllowed`], except
+    /// `OPTIONS` requests, which are answered automatically with this list.
+    ///
+    /// Add [`Method::Post`] to this list if you implement
+    /// [`Handle::handle_post()`], and so on.
+    const METHODS: &'static [Method] = &[Method::Get, Method::Head];
 
     /// Called for each GET request.
     ///
+    /// - context - The HTTP method, headers, etc.
     /// - path - The part of the requested URL relative to `base_url`.
     ///   For example if this Handle is at `http://example.com/foo` and
     ///   the client requests is `http://example.com/foo/bar/baz` then `path`
@@ -89,11 +302,121 @@ pub trait Handle {
     /// Note that the [`String`]s in `path` and `params` might contain special
     /// characters such as `/` and `?`, and non-ASCII characters. Be careful if
     /// you construct filesystem paths from these `String`s.
+    /// [`StaticDir`](crate::StaticDir) does so safely.
     fn handle_get(
         &mut self,
+        context: &Context,
         path: &[String],
         params: Self::Params,
     ) -> self::Result;
+
+    /// Called for each HEAD request. The response body is not sent, but the
+    /// headers are, including `Content-Length`.
+    ///
+    /// The arguments are as for [`Handle::handle_get()`]. The default
+    /// implementation calls `handle_get()`.
+    fn handle_head(
+        &mut self,
+        context: &Context,
+        path: &[String],
+        params: Self::Params,
+    ) -> self::Result {
+        self.handle_get(context, path, params)
+    }
+
+    /// Called for each POST request.
+    ///
+    /// - context, path - As for [`Handle::handle_get()`].
+    /// - params - The form fields parsed from the request body, which must be
+    ///   of type `application/x-www-form-urlencoded` or `multipart/form-data`.
+    ///   The URL request parameters are ignored.
+    /// - uploads - The files in a `multipart/form-data` request body.
+    ///
+    /// The default implementation returns [`HttpError::MethodNotAllowed`].
+    fn handle_post(
+        &mut self,
+        _context: &Context,
+        _path: &[String],
+        _params: Self::Params,
+        _uploads: Vec<Upload>,
+    ) -> self::Result {
+        Err(HttpError::MethodNotAllowed(Self::METHODS))
+    }
+
+    /// Called for each PUT request.
+    ///
+    /// - context, path, params - As for [`Handle::handle_get()`].
+    /// - body - The request body, which is not parsed.
+    ///
+    /// The default implementation returns [`HttpError::MethodNotAllowed`].
+    fn handle_put(
+        &mut self,
+        _context: &Context,
+        _path: &[String],
+        _params: Self::Params,
+        _body: &mut Body,
+    ) -> self::Result {
+        Err(HttpError::MethodNotAllowed(Self::METHODS))
+    }
+
+    /// Called for each PATCH request. The arguments are as for
+    /// [`Handle::handle_put()`].
+    ///
+    /// The default implementation returns [`HttpError::MethodNotAllowed`].
+    fn handle_patch(
+        &mut self,
+        _context: &Context,
+        _path: &[String],
+        _params: Self::Params,
+        _body: &mut Body,
+    ) -> self::Result {
+        Err(HttpError::MethodNotAllowed(Self::METHODS))
+    }
+
+    /// Called for each DELETE request. The arguments are as for
+    /// [`Handle::handle_put()`]. The body is usually empty.
+    ///
+    /// The default implementation returns [`HttpError::MethodNotAllowed`].
+    fn handle_delete(
+        &mut self,
+        _context: &Context,
+        _path: &[String],
+        _params: Self::Params,
+        _body: &mut Body,
+    ) -> self::Result {
+        Err(HttpError::MethodNotAllowed(Self::METHODS))
+    }
+}
+
+/// Constructs a [`Handle::Params`] from URL request parameters or form
+/// fields, or rejects the request.
+///
+/// This is implemented for every `FromIterator<(String, String)>`, which
+/// accepts any parameters. Implement it yourself to validate them:
+///
+/// ```
+/// use petite_http::{HttpError, TryFromParams};
+///
+/// struct Params {name: String}
+///
+/// impl TryFromParams for Params {
+///     fn try_from_params(params: impl IntoIterator<Item = (String, String)>) -> Result<Self, HttpError> {
+///         params.into_iter().find(|(key, value)| key == "name" && "" != value).map(
+///             |(_, name)| Self {name}
+///         ).ok_or_else(|| HttpError::Invalid("Please tell me your name.".into()))
+///     }
+/// }
+/// ```
+pub trait TryFromParams: Sized {
+    /// Fails with an [`HttpError`], typically [`HttpError::Invalid`], to
+    /// reject the request without calling the [`Handle`].
+    fn try_from_params(params: impl IntoIterator<Item = (String, String)>) -> std::result::Result<Self, HttpError>;
+}
+
+impl<P: FromIterator<(String, String)>> TryFromParams for P {
+    fn try_from_params(params: impl IntoIterator<Item = (String, String)>) -> std::result::Result<Self, HttpError> {
+        Ok(params.into_iter().collect())
+    }
 }
 
 // ----------------------------------------------------------------------------
@@ -110,175 +433,1208 @@ pub trait Route {
     /// If the path is sufficient to generate a response, just return it. If,
     /// say, the URL query parameters are needed to generate a response, then
     /// pass an implementation of [`Handle`] to `callback`.
+    ///
+    /// `context` describes the rest of the request, in case it is relevant.
+    ///
+    /// `callback` checks the HTTP method against [`Handle::METHODS`]. If you
+    /// return a response without calling it, check the method yourself with
+    /// [`Context::check_method()`]:
+    ///
+    /// ```
+    /// # use petite_http::{Context, HttpOkay, Method, Result};
+    /// fn about_page(context: &Context) -> Result {
+    ///     if let Some(response) = context.check_method(&[Method::Get, Method::Head])? { return Ok(response); }
+    ///     Ok(HttpOkay::Html(Box::new("About us")))
+    /// }
+    /// ```
     fn route(
         &mut self,
+        context: &Context,
         path: &[String],
         callback: impl Callback,
     ) -> self::Result;
+
+    /// Optionally generate the body of an error response, for example an
+    /// HTML page constructed using [`html::Template`].
+    ///
+    /// The status code and any headers implied by `error` are added to the
+    /// result. The default implementation returns `None`, which selects a
+    /// short plain text message.
+    fn render_error(&mut self, _error: &HttpError) -> Option<HttpOkay> { None }
 }
 
 impl<H: Handle> Route for H {
-    fn route(&mut self, _path: &[String], callback: impl Callback) -> self::Result {
+    fn route(&mut self, _context: &Context, _path: &[String], callback: impl Callback) -> self::Result {
         callback.handle_with(self)
     }
 }
 
+/// Like [`Route`], but takes `&self` so that requests can be handled
+/// concurrently by [`start_pool()`] and [`spawn_pool()`].
+///
+/// Any `Route` can be shared by wrapping it in a [`Mutex`], but then only
+/// one thread can route at a time.
+pub trait SharedRoute {
+    /// As for [`Route::route()`].
+    fn route(
+        &self,
+        context: &Context,
+        path: &[String],
+        callback: impl Callback,
+    ) -> self::Result;
+
+    /// As for [`Route::render_error()`].
+    fn render_error(&self, _error: &HttpError) -> Option<HttpOkay> { None }
+}
+
+impl<R: Route> SharedRoute for Mutex<R> {
+    fn route(&self, context: &Context, path: &[String], callback: impl Callback) -> self::Result {
+        self.lock().unwrap_or_else(PoisonError::into_inner).route(context, path, callback)
+    }
+
+    fn render_error(&self, error: &HttpError) -> Option<HttpOkay> {
+        self.lock().unwrap_or_else(PoisonError::into_inner).render_error(error)
+    }
+}
+
+/// Adapts a [`SharedRoute`] to be used as a [`Route`].
+struct Shared<'a, R>(&'a R);
+
+impl<'a, R: SharedRoute> Route for Shared<'a, R> {
+    fn route(&mut self, context: &Context, path: &[String], callback: impl Callback) -> self::Result {
+        self.0.route(context, path, callback)
+    }
+
+    fn render_error(&mut self, error: &HttpError) -> Option<HttpOkay> {
+        self.0.render_error(error)
+    }
+}
+
 // ----------------------------------------------------------------------------
 
 /// The name of the HTTP `Content-Type` header.
-const CONTENT_TYPE: &'static [u8] = b"Content-Type";
+const CONTENT_TYPE: &'static str = "Content-Type";
 
 /// The name of the HTTP `Location` header.
-const LOCATION: &'static [u8] = b"Location";
+const LOCATION: &'static str = "Location";
+
+/// The name of the HTTP `Allow` header.
+const ALLOW: &'static str = "Allow";
+
+/// The name of the HTTP `Retry-After` header.
+const RETRY_AFTER: &'static str = "Retry-After";
+
+/// The name of the HTTP `ETag` header.
+const ETAG: &'static str = "ETag";
+
+/// The name of the HTTP `Last-Modified` header.
+const LAST_MODIFIED: &'static str = "Last-Modified";
+
+/// The name of the HTTP `Accept-Ranges` header.
+const ACCEPT_RANGES: &'static str = "Accept-Ranges";
+
+/// The name of the HTTP `Content-Range` header.
+const CONTENT_RANGE: &'static str = "Content-Range";
+
+/// The headers of a response that are repeated in a `304 Not Modified`
+/// response.
+const NOT_MODIFIED_HEADERS: [&'static str; 6] = ["Cache-Control", "Content-Location", "ETag", "Expires", "Last-Modified", "Vary"];
+
+/// The headers that describe the connection or the length of the body, which
+/// handlers cannot set.
+const RESERVED_HEADERS: [&'static str; 5] = ["Connection", "Content-Length", "Trailer", "Transfer-Encoding", "Upgrade"];
+
+/// Construct an HTTP header, checking that `key` is a token and that `value`
+/// is printable ASCII.
+fn header(key: &str, value: &str) -> std::result::Result<Header, HttpError> {
+    let is_valid = !key.is_empty() && key.bytes().all(|b| b.is_ascii_graphic() && b != b':')
+        && value.bytes().all(|b| b'\t' == b || (b' '..=b'~').contains(&b));
+    Header::from_bytes(key, value).ok().filter(|_| is_valid).ok_or_else(
+        || HttpError::Error(format!("Invalid HTTP header {key:?}: {value:?}").into())
+    )
+}
+
+/// The error returned by [`Body`] when the request body is too long.
+#[derive(Debug)]
+struct TooLong;
 
-/// Construct an HTTP header.
-fn header(key: &'static [u8], value: &[u8]) -> tiny_http::Header {
-    Header::from_bytes(key, value).unwrap() // depends only on data fixed at compile time
+impl fmt::Display for TooLong {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The request body is too long")
+    }
 }
 
-/// Get the MIME type of a `File` and rewind the `File`.
-fn get_mime_type(file: &mut File) -> std::io::Result<&'static [u8]> {
-    let mime_type = tika_magic::from_file(&file).ok_or_else(|| std::io::Error::other("error getting MIME type"))?;
-    file.rewind()?;
-    Ok(mime_type.as_bytes())
+impl Error for TooLong {}
+
+/// The body of a request, which can be read using [`Read`].
+///
+/// Reading fails if the body is longer than the maximum length, which is set
+/// by [`ServerBuilder::max_body_length()`] and can be lowered using
+/// [`Body::limit()`]. The error converts to [`HttpError::PayloadTooLarge`].
+pub struct Body<'a> {
+    reader: &'a mut dyn Read,
+
+    /// The `Content-Length` of the request, if known.
+    length: Option<u64>,
+
+    max_length: u64,
+
+    /// The number of bytes read so far.
+    position: u64,
 }
 
-struct Server {
-    /// Web server.
-    pub server: tiny_http::Server,
+impl<'a> Body<'a> {
+    /// Fails if the `Content-Length` of the request exceeds `max_length`.
+    fn new(
+        context: &Context,
+        reader: &'a mut dyn Read,
+        max_length: u64,
+    ) -> std::result::Result<Self, HttpError> {
+        let length = context.header("Content-Length").map(
+            |length| length.trim().parse::<u64>().map_err(
+                |_| HttpError::Invalid(format!("Invalid Content-Length {:?}", length))
+            )
+        ).transpose()?;
+        let mut ret = Self {reader, length, max_length: u64::MAX, position: 0};
+        ret.limit(max_length)?;
+        Ok(ret)
+    }
 
-    /// The local URL that `server` serves.
-    pub server_url: Url,
+    /// The length of the body declared by the client, if any. It is unknown
+    /// if the body is sent in chunks.
+    pub fn length(&self) -> Option<u64> { self.length }
 
-    /// The publicly visible external URL, which may differ from `server_url`.
-    pub base_url: Url,
+    /// The maximum length of the body.
+    pub fn max_length(&self) -> u64 { self.max_length }
+
+    /// Reduce the maximum length of the body to `max_length`. This has no
+    /// effect if the maximum length is already smaller.
+    ///
+    /// Fails with [`HttpError::PayloadTooLarge`] if the declared length of
+    /// the body exceeds `max_length`.
+    pub fn limit(&mut self, max_length: u64) -> std::result::Result<(), HttpError> {
+        self.max_length = self.max_length.min(max_length);
+        if self.length.is_some_and(|length| length > self.max_length) { return Err(HttpError::PayloadTooLarge); }
+        Ok(())
+    }
+
+    /// Read the rest of the body.
+    pub fn bytes(&mut self) -> std::result::Result<Vec<u8>, HttpError> {
+        let mut ret = Vec::new();
+        self.read_to_end(&mut ret)?;
+        Ok(ret)
+    }
+
+    /// Read the rest of the body, which must be UTF-8. Fails with
+    /// [`HttpError::Invalid`] if it isn't.
+    pub fn text(&mut self) -> std::result::Result<String, HttpError> {
+        String::from_utf8(self.bytes()?).map_err(|_| HttpError::Invalid("The request body is not UTF-8".into()))
+    }
+
+    /// Read the rest of the body and parse it as JSON. Fails with
+    /// [`HttpError::Invalid`] if it is not valid JSON or does not match `T`.
+    /// Requires the `json` feature.
+    ///
+    /// The `Content-Type` of the request is not checked.
+    #[cfg(feature = "json")]
+    pub fn json<T: serde::de::DeserializeOwned>(&mut self) -> std::result::Result<T, HttpError> {
+        serde_json::from_slice(&self.bytes()?).map_err(|e| HttpError::Invalid(format!("Invalid JSON: {}", e)))
+    }
 }
 
-impl Server {
-    fn new(server_address: &str, base_url: Option<&str>) -> Self {
-        let server_url = &format!("http://{}/", server_address);
-        let base_url = base_url.unwrap_or_else(|| server_url);
-        assert!(base_url.ends_with('/'));
-        Server {
-            server: tiny_http::Server::http(server_address).expect("Could not create the web server"),
-            server_url: Url::parse(server_url).expect("Could not parse the server URL"),
-            base_url: Url::parse(base_url).expect("Could not parse the base URL"),
+impl<'a> Read for Body<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        // Read one byte more than allowed, to detect an excessive body.
+        let remaining = self.max_length.saturating_sub(self.position);
+        let limit = buf.len().min(usize::try_from(remaining.saturating_add(1)).unwrap_or(usize::MAX));
+        let n = self.reader.read(&mut buf[..limit])?;
+        if n as u64 > remaining { return Err(std::io::Error::other(TooLong)); }
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+/// Add `charset=utf-8` to `content_type` if it is a textual type without a
+/// `charset`, because Rust `String`s are always UTF-8.
+fn with_utf8(content_type: MimeType) -> MimeType {
+    let subtype = content_type.subtype();
+    let is_textual = "text" == content_type.type_() || "xml" == subtype || subtype.ends_with("+xml");
+    if !is_textual || content_type.param("charset").is_some() { return content_type; }
+    content_type.with_param("charset", "utf-8").expect("charset=utf-8 is valid")
+}
+
+/// Returns [`Handle::METHODS`] for `handler`.
+fn allowed_methods<H: Handle>(_handler: &H) -> &'static [Method] { H::METHODS }
+
+/// The value of the `Allow` header for a resource that allows `methods`,
+/// which always include `OPTIONS`.
+fn allow(methods: &[Method]) -> String {
+    let options = Some(&Method::Options).filter(|options| !methods.contains(options));
+    let methods: Vec<String> = methods.iter().chain(options).map(Method::to_string).collect();
+    methods.join(", ")
+}
+
+/// Read and parse an `application/x-www-form-urlencoded` or
+/// `multipart/form-data` request body.
+///
+/// - max_uploads - The maximum number of files in the body.
+fn read_form<P: TryFromParams>(
+    context: &Context,
+    body: &mut Body,
+    max_uploads: usize,
+) -> std::result::Result<(P, Vec<Upload>), HttpError> {
+    let content_type = context.content_type().ok_or(HttpError::UnsupportedMediaType)?;
+    if content_type.is(&content_types::FORM) {
+        let data = body.bytes()?;
+        let params = P::try_from_params(url::form_urlencoded::parse(&data).map(
+            |(key, value)| (key.into_owned(), value.into_owned())
+        ))?;
+        Ok((params, Vec::new()))
+    } else if content_type.is(&content_types::MULTIPART_FORM) {
+        let (fields, uploads) = multipart::parse(body, &content_type, max_uploads)?;
+        Ok((P::try_from_params(fields)?, uploads))
+    } else {
+        Err(HttpError::UnsupportedMediaType)
+    }
+}
+
+/// The reasons why a web server might fail to start.
+#[derive(Debug)]
+pub enum StartError {
+    /// Could not listen on the server address, e.g. because it is in use.
+    Bind(Box<dyn Error + Send + Sync>),
+
+    /// The server address does not form a valid URL.
+    InvalidServerUrl(url::ParseError),
+
+    /// The base URL is not a valid URL.
+    InvalidBaseUrl(url::ParseError),
+
+    /// The base URL does not end with `/`.
+    BaseUrlWithoutSlash(String),
+}
+
+impl fmt::Display for StartError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Bind(e) => write!(f, "Could not create the web server: {}", e),
+            Self::InvalidServerUrl(e) => write!(f, "Could not parse the server URL: {}", e),
+            Self::InvalidBaseUrl(e) => write!(f, "Could not parse the base URL: {}", e),
+            Self::BaseUrlWithoutSlash(url) => write!(f, "The base URL {:?} does not end with '/'", url),
+        }
+    }
+}
+
+impl Error for StartError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Bind(e) => Some(&**e),
+            Self::InvalidServerUrl(e) | Self::InvalidBaseUrl(e) => Some(e),
+            Self::BaseUrlWithoutSlash(_) => None,
+        }
+    }
+}
+
+/// Sends a log message somewhere.
+type Log = Box<dyn Fn(&str) + Send + Sync>;
+
+/// Renders an error page, as for [`Route::render_error()`].
+type ErrorPage = Box<dyn Fn(&HttpError) -> Option<HttpOkay> + Send + Sync>;
+
+/// The parts of an HTTP request that are needed to handle it.
+pub(crate) struct Incoming<'a> {
+    pub method: Method,
+
+    /// The request target, e.g. `/foo?bar=baz`.
+    pub url: String,
+
+    pub headers: Vec<Header>,
+    pub remote_addr: Option<SocketAddr>,
+    pub body: &'a mut dyn Read,
+}
+
+/// The headers of a `GET` request that make it conditional, or select part
+/// of the response.
+struct GetHeaders {
+    if_none_match: Option<String>,
+    if_modified_since: Option<String>,
+    range: Option<String>,
+    if_range: Option<String>,
+}
+
+impl GetHeaders {
+    /// Returns `None` if `request` is not a `GET` or `HEAD` request.
+    fn new(request: &Incoming) -> Option<Self> {
+        if !matches!(request.method, Method::Get | Method::Head) { return None; }
+        let header = |name| request.headers.iter().find(|h| h.field.equiv(name)).map(|h| h.value.to_string());
+        // `Range` only applies to `GET`, and `If-Range` only with `Range`.
+        let is_get = Method::Get == request.method;
+        Some(Self {
+            if_none_match: header("If-None-Match"),
+            if_modified_since: header("If-Modified-Since"),
+            range: if is_get { header("Range") } else { None },
+            if_range: if is_get { header("If-Range") } else { None },
+        })
+    }
+
+    /// Returns `true` if the client's copy of `response` is up to date.
+    fn is_not_modified<R: Read>(&self, response: &Response<R>) -> bool {
+        if ![200, 206].contains(&response.status_code().0) { return false; }
+        let header = |name| response.headers().iter().find(|h| h.field.equiv(name)).map(|h| h.value.as_str());
+        if let Some(if_none_match) = &self.if_none_match {
+            // `If-Modified-Since` is ignored in this case.
+            if "*" == if_none_match.trim() { return true; }
+            let Some(etag) = header("ETag") else { return false; };
+            return if_none_match.split(',').any(|tag| weak_eq(tag, etag));
+        }
+        if let Some(if_modified_since) = &self.if_modified_since
+            && let Some(last_modified) = header("Last-Modified")
+            && let Ok(if_modified_since) = httpdate::parse_http_date(if_modified_since)
+            && let Ok(last_modified) = httpdate::parse_http_date(last_modified)
+        {
+            return last_modified <= if_modified_since;
+        }
+        false
+    }
+
+    /// Returns the inclusive byte ranges of `response` that are requested, or
+    /// `None` if the whole response is requested.
+    ///
+    /// Fails with [`HttpError::RangeNotSatisfiable`] if none of the requested
+    /// ranges overlaps the response body, which is `length` bytes long.
+    fn ranges<R: Read>(
+        &self,
+        response: &Response<R>,
+        length: u64,
+    ) -> std::result::Result<Option<Vec<(u64, u64)>>, HttpError> {
+        let Some(range) = &self.range else { return Ok(None); };
+        if let Some(if_range) = &self.if_range {
+            // The ranges apply only to the version of the response identified by
+            // a strong `ETag` or the exact `Last-Modified` date.
+            let if_range = if_range.trim();
+            let header = |name| response.headers().iter().find(|h| h.field.equiv(name)).map(|h| h.value.as_str());
+            let is_current = if if_range.starts_with('"') {
+                header("ETag").is_some_and(|etag| etag == if_range)
+            } else {
+                header("Last-Modified").is_some_and(|date| date == if_range)
+            };
+            if !is_current { return Ok(None); }
+        }
+        // Ignore a `Range` header that is not understood.
+        let Some(specs) = range.trim().strip_prefix("bytes=") else { return Ok(None); };
+        let mut ranges = Vec::new();
+        for spec in specs.split(',') {
+            let Some((first, last)) = spec.trim().split_once('-') else { return Ok(None); };
+            let range = match (first.parse::<u64>(), last.parse::<u64>()) {
+                (Ok(first), Ok(last)) if first <= last => (first, last.min(length.saturating_sub(1))),
+                (Ok(first), _) if "" == last => (first, length.saturating_sub(1)),
+                (_, Ok(suffix)) if "" == first => (length.saturating_sub(suffix), length.saturating_sub(1)),
+                _ => { return Ok(None); },
+            };
+            // Ignore ranges outside the body, including suffixes of length zero.
+            if range.0 < length { ranges.push(range); }
+        }
+        if ranges.is_empty() { return Err(HttpError::RangeNotSatisfiable(length)); }
+        // Merge overlapping and adjacent ranges, so that they can't be used to
+        // amplify the response.
+        if ranges.len() > 1 {
+            ranges.sort();
+            let mut merged: Vec<(u64, u64)> = Vec::new();
+            for (first, last) in ranges {
+                match merged.last_mut() {
+                    Some(previous) if first <= previous.1 + 1 => { previous.1 = previous.1.max(last); },
+                    _ => { merged.push((first, last)); },
+                }
+            }
+            ranges = merged;
+        }
+        Ok(Some(ranges))
+    }
+}
+
+/// Construct a `206 Partial Content` response containing the inclusive byte
+/// `ranges` of the file in `response`, which is `length` bytes long.
+fn partial_response(
+    response: Response<File>,
+    content_type: &MimeType,
+    length: u64,
+    ranges: Vec<(u64, u64)>,
+) -> std::result::Result<ResponseBox, HttpError> {
+    let mut headers = response.headers().to_vec();
+    let file = response.into_reader();
+    let mut segments = VecDeque::new();
+    if let [(first, last)] = ranges[..] {
+        headers.push(header(CONTENT_RANGE, &format!("bytes {}-{}/{}", first, last, length))?);
+        segments.push_back(Segment::File {start: first, length: last + 1 - first});
+    } else {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+        let boundary = format!("{:x}{:x}", nanos, length);
+        headers.retain(|h| !h.field.equiv("Content-Type"));
+        headers.push(header(CONTENT_TYPE, &format!("multipart/byteranges; boundary={}", boundary))?);
+        for (first, last) in ranges {
+            let part_headers = format!(
+                "\r\n--{}\r\nContent-Type: {}\r\nContent-Range: bytes {}-{}/{}\r\n\r\n",
+                boundary, content_type, first, last, length,
+            );
+            segments.push_back(Segment::Data(Cursor::new(part_headers.into_bytes())));
+            segments.push_back(Segment::File {start: first, length: last + 1 - first});
         }
+        segments.push_back(Segment::Data(Cursor::new(format!("\r\n--{}--\r\n", boundary).into_bytes())));
     }
+    let segments = Segments {file, segments};
+    let body_length = segments.segments.iter().map(|segment| match segment {
+        Segment::Data(data) => data.get_ref().len() as u64,
+        Segment::File {length, ..} => *length,
+    }).sum::<u64>();
+    Ok(Response::new(StatusCode(206), headers, segments, usize::try_from(body_length).ok(), None).boxed())
+}
+
+/// A part of a response body.
+enum Segment {
+    Data(Cursor<Vec<u8>>),
+
+    /// Part of [`Segments::file`].
+    File {start: u64, length: u64},
+}
+
+/// Reads a sequence of [`Segment`]s.
+struct Segments {
+    file: File,
+    segments: VecDeque<Segment>,
+}
+
+impl Read for Segments {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while let Some(segment) = self.segments.front_mut() {
+            let n = match segment {
+                Segment::Data(data) => data.read(buf)?,
+                Segment::File {start, length} => {
+                    let limit = buf.len().min(usize::try_from(*length).unwrap_or(usize::MAX));
+                    self.file.seek(SeekFrom::Start(*start))?;
+                    let n = self.file.read(&mut buf[..limit])?;
+                    if 0 == n && limit > 0 { Err(std::io::ErrorKind::UnexpectedEof)?; }
+                    *start += n as u64;
+                    *length -= n as u64;
+                    n
+                },
+            };
+            if n > 0 || buf.is_empty() { return Ok(n); }
+            self.segments.pop_front();
+        }
+        Ok(0)
+    }
+}
+
+/// Returns `true` if entity tags `a` and `b` are equal, ignoring whether
+/// they are weak.
+fn weak_eq(a: &str, b: &str) -> bool {
+    fn strip(tag: &str) -> &str {
+        let tag = tag.trim();
+        tag.strip_prefix("W/").unwrap_or(tag)
+    }
+    strip(a) == strip(b)
+}
+
+/// Construct a `304 Not Modified` response to replace `response`.
+fn not_modified(response: &ResponseBox) -> ResponseBox {
+    let mut ret = Response::empty(304).boxed();
+    for h in response.headers() {
+        if NOT_MODIFIED_HEADERS.iter().any(|&name| h.field.equiv(name)) { ret.add_header(h.clone()); }
+    }
+    ret
+}
+
+/// The settings of a web server, which determine how requests are handled.
+pub(crate) struct Config {
+    /// The local URL that the server serves.
+    server_url: Url,
+
+    /// The publicly visible external URL, which may differ from `server_url`.
+    base_url: Url,
 
+    /// The maximum length of a request body.
+    max_body_length: u64,
+
+    /// The maximum number of files in a request body.
+    max_uploads: usize,
+
+    /// The longest time a request may wait to be handled.
+    queue_timeout: Option<Duration>,
+
+    /// Where to send log messages.
+    log: Log,
+
+    /// Renders error pages if the router doesn't.
+    error_page: Option<ErrorPage>,
+}
+
+impl Config {
     fn handle_request(
         &self,
         router: &mut impl Route,
-        request: &mut Request,
+        request: Incoming,
     ) -> self::Result {
-        let request_url = self.server_url.join(request.url())?;
-        let relative_url = self.server_url.make_relative(&request_url).unwrap(); // By construction.
-        println!("{} {}", request.remote_addr().unwrap().ip(), relative_url);
+        let request_url = self.server_url.join(&request.url)?;
+        // The request target can name another host, e.g. `//example.com/`.
+        let relative_url = self.server_url.make_relative(&request_url).ok_or_else(
+            || HttpError::Invalid("The URL is not on this server".into())
+        )?;
+        let ip = request.remote_addr.map_or("-".into(), |addr| addr.ip().to_string());
+        (self.log)(&format!("{} {}", ip, relative_url));
+        let context = Context {
+            method: request.method,
+            url: self.base_url.join(&relative_url)?,
+            base_url: self.base_url.clone(),
+            headers: request.headers,
+            remote_addr: request.remote_addr,
+        };
         // Parse the path segments.
-        let mut path: Vec<String> = request_url.path_segments().ok_or(HttpError::Invalid)?.map(
-            |s| percent_encoding::percent_decode(s.as_bytes()).decode_utf8().unwrap().into_owned()
-        ).collect();
-        if let Some(last) = path.last() {
-            if "" == last { path.pop(); }
-        }
+        let mut path: Vec<String> = request_url.path_segments().ok_or_else(
+            || HttpError::Invalid("The URL has no path".into())
+        )?.map(
+            |s| percent_encoding::percent_decode(s.as_bytes()).decode_utf8().map(|s| s.into_owned())
+        ).collect::<std::result::Result<_, _>>().map_err(|_| HttpError::Invalid("The URL path is not UTF-8".into()))?;
+        if let Some(last) = path.last() && "" == last { path.pop(); }
         // Make a callback.
         struct Callback<'a> {
+            context: &'a Context,
             path: &'a [String],
-            method: &'a Method,
+            body: Body<'a>,
             url: Url,
+            max_uploads: usize,
         }
         impl<'a> self::Callback for Callback<'a> {
-            fn handle_with(self, handler: &mut impl Handle) -> self::Result {
+            fn handle_with(mut self, handler: &mut impl Handle) -> self::Result {
+                let methods = allowed_methods(handler);
+                if let Some(response) = self.context.check_method(methods)? { return Ok(response); }
+                // Dispatch based on HTTP method.
+                if Method::Post == *self.context.method() {
+                    let (params, uploads) = read_form(self.context, &mut self.body, self.max_uploads)?;
+                    return handler.handle_post(self.context, self.path, params, uploads);
+                }
                 // Parse the query parameters.
-                let params = self.url.query_pairs().map(
+                let params = TryFromParams::try_from_params(self.url.query_pairs().map(
                     |(key, value)| (
                         key.as_ref().into(),
                         value.as_ref().into(),
                     )
-                ).collect();
-                // Dispatch based on HTTP method.
-                match self.method {
-                    Method::Get => handler.handle_get(self.path, params),
-                    _ => Err(HttpError::Invalid),
+                ))?;
+                match self.context.method() {
+                    Method::Get => handler.handle_get(self.context, self.path, params),
+                    Method::Head => handler.handle_head(self.context, self.path, params),
+                    Method::Put => handler.handle_put(self.context, self.path, params, &mut self.body),
+                    Method::Patch => handler.handle_patch(self.context, self.path, params, &mut self.body),
+                    Method::Delete => handler.handle_delete(self.context, self.path, params, &mut self.body),
+                    _ => Err(HttpError::MethodNotAllowed(methods)),
                 }
             }
         }
-        router.route(&*path, Callback {
-            path: &*path,
-            method: request.method(),
+        let body = Body::new(&context, request.body, self.max_body_length)?;
+        router.route(&context, &path, Callback {
+            context: &context,
+            path: &path,
+            body,
             url: request_url,
+            max_uploads: self.max_uploads,
         })
     }
 
-    /// Handle requests for ever.
-    fn handle_requests(&self, mut router: impl Route) -> ! {
-        for mut request in self.server.incoming_requests() {
-            match self.handle_request(&mut router, &mut request) {
-                Ok(HttpOkay::File {mut file, content_type}) => {
-                    let mime_type = content_type.ok_or(()).or_else(|()| get_mime_type(&mut file));
-                    match mime_type {
-                        Ok(mime_type) => {
-                            let header = header(CONTENT_TYPE, mime_type);
-                            request.respond(Response::from_file(file).with_header(header))
-                        },
-                        Err(e) => {
-                            println!("{}", e);
-                            request.respond(Response::from_string("Server error").with_status_code(500))
-                        }
-                    }
-                },
-                Ok(HttpOkay::Html(text)) => {
-                    let header = header(CONTENT_TYPE, content_types::HTML);
-                    let html::Raw(escaped_text) = text.to_html();
-                    request.respond(Response::from_string(escaped_text).with_header(header))
-                },
-                Ok(HttpOkay::Chars {data, content_type}) => {
-                    let header = header(CONTENT_TYPE, content_type);
-                    request.respond(Response::from_string(data).with_header(header))
-                },
-                Ok(HttpOkay::Bytes {data, content_type}) => {
-                    let header = header(CONTENT_TYPE, content_type);
-                    request.respond(Response::from_data(data).with_header(header))
-                },
-                Ok(HttpOkay::Redirect(relative_url)) => {
-                    match self.base_url.join(&relative_url) {
-                        Ok(absolute_url) => {
-                            request.respond(
-                                Response::from_string("Temporary Redirect").with_status_code(307)
-                                    .with_header(header(LOCATION, absolute_url.as_str().as_bytes()))
-                            )
-                        },
-                        Err(e) => {
-                            println!("Error contructing URL for redirect: {}", e);
-                            request.respond(Response::from_string("Server error").with_status_code(500))
-                        },
+    /// Convert an `HttpOkay` into a `tiny_http` response.
+    ///
+    /// - get - The headers of a `GET` or `HEAD` request, which may select
+    ///   part of a file.
+    fn okay_response(&self, okay: HttpOkay, get: Option<&GetHeaders>) -> std::result::Result<ResponseBox, HttpError> {
+        Ok(match okay {
+            HttpOkay::File {mut file, content_type} => {
+                let mime_type = content_type.ok_or(()).or_else(|()| content_types::sniff(&mut file))?;
+                let metadata = file.metadata()?;
+                let length = metadata.len();
+                let mut response = Response::from_file(file).with_header(header(CONTENT_TYPE, mime_type.as_str())?);
+                response.add_header(header(ACCEPT_RANGES, "bytes")?);
+                if let Ok(modified) = metadata.modified() {
+                    response.add_header(header(LAST_MODIFIED, &httpdate::fmt_http_date(modified))?);
+                    let age = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+                    // Weak, because the file could change without changing its length or
+                    // modification time.
+                    let etag = format!("W/\"{:x}-{:x}.{:x}\"", length, age.as_secs(), age.subsec_nanos());
+                    response.add_header(header(ETAG, &etag)?);
+                }
+                if let Some(get) = get
+                    && !get.is_not_modified(&response)
+                    && let Some(ranges) = get.ranges(&response, length)?
+                {
+                    return partial_response(response, &mime_type, length, ranges);
+                }
+                response.boxed()
+            },
+            HttpOkay::Html(text) => {
+                let header = header(CONTENT_TYPE, with_utf8(content_types::HTML).as_str())?;
+                let html::Raw(escaped_text) = text.to_html();
+                Response::from_string(escaped_text).with_header(header).boxed()
+            },
+            HttpOkay::Chars {data, content_type} => {
+                Response::from_string(data).with_header(header(CONTENT_TYPE, with_utf8(content_type).as_str())?).boxed()
+            },
+            HttpOkay::Bytes {data, content_type} => {
+                Response::from_data(data).with_header(header(CONTENT_TYPE, content_type.as_str())?).boxed()
+            },
+            HttpOkay::Redirect(relative_url) => {
+                let absolute_url = self.base_url.join(&relative_url)?;
+                Response::from_string("Temporary Redirect").with_status_code(307)
+                    .with_header(header(LOCATION, absolute_url.as_str())?).boxed()
+            },
+            HttpOkay::Empty => Response::empty(204).boxed(),
+            HttpOkay::Custom {body, status, headers} => {
+                // A different status code probably means that the body is not the
+                // requested resource, so ranges don't apply.
+                let mut response = self.okay_response(*body, get.filter(|_| status.is_none()))?;
+                if let Some(status) = status { response = response.with_status_code(status); }
+                for (key, value) in headers {
+                    if RESERVED_HEADERS.iter().any(|name| name.eq_ignore_ascii_case(key)) {
+                        return Err(HttpError::Error(format!("The {key:?} header cannot be set").into()));
                     }
+                    response.add_header(header(key, &value)?);
+                }
+                response
+            },
+        })
+    }
+
+    /// Convert an `HttpError` into a `tiny_http` response.
+    ///
+    /// - page - The body of the response, if not the default.
+    fn error_response(&self, error: HttpError, page: Option<HttpOkay>) -> ResponseBox {
+        let (status, text) = match &error {
+            HttpError::Invalid(_) => (400, "Invalid request"),
+            HttpError::Unauthorized => (401, "Unauthorized"),
+            HttpError::Forbidden => (403, "Forbidden"),
+            HttpError::NotFound => (404, "Not found"),
+            HttpError::MethodNotAllowed(_) => (405, "Method not allowed"),
+            HttpError::NotAcceptable => (406, "Not acceptable"),
+            HttpError::Conflict => (409, "Conflict"),
+            HttpError::Gone => (410, "Gone"),
+            HttpError::PayloadTooLarge => (413, "Payload too large"),
+            HttpError::UnsupportedMediaType => (415, "Unsupported media type"),
+            HttpError::RangeNotSatisfiable(_) => (416, "Range not satisfiable"),
+            HttpError::TooManyRequests => (429, "Too many requests"),
+            HttpError::ServiceUnavailable(_) => (503, "Service unavailable"),
+            HttpError::Error(e) => {
+                (self.log)(&format!("Error: {}", e));
+                (500, "Server error")
+            },
+        };
+        let text = match &error {
+            HttpError::Invalid(message) if "" != message => format!("{}: {}", text, message),
+            _ => text.into(),
+        };
+        let page = page.or_else(|| self.error_page.as_ref().and_then(|render| render(&error)));
+        let page = page.map(|page| self.okay_response(page, None)).transpose().unwrap_or_else(|e| {
+            (self.log)(&format!("Error rendering error page: {}", e));
+            None
+        });
+        let mut response = page.unwrap_or_else(
+            || Response::from_string(text).boxed()
+        ).with_status_code(status);
+        let extra_header = match error {
+            HttpError::MethodNotAllowed(methods) => Some(header(ALLOW, &allow(methods))),
+            HttpError::RangeNotSatisfiable(length) => Some(header(CONTENT_RANGE, &format!("bytes */{}", length))),
+            HttpError::ServiceUnavailable(Some(delay)) => Some(header(RETRY_AFTER, &delay.as_secs().to_string())),
+            _ => None,
+        };
+        match extra_header {
+            Some(Ok(header)) => { response.add_header(header); },
+            Some(Err(e)) => { (self.log)(&format!("Error: {}", e)); },
+            None => {},
+        }
+        response
+    }
+
+    /// Handle `request`, which was received at time `received`, and return
+    /// the response.
+    pub(crate) fn response(&self, router: &mut impl Route, request: Incoming, received: Instant) -> ResponseBox {
+        let get = GetHeaders::new(&request);
+        let result = if self.queue_timeout.is_some_and(|timeout| received.elapsed() > timeout) {
+            (self.log)(&format!("Timed out waiting to handle {}", request.url));
+            Err(HttpError::ServiceUnavailable(None))
+        } else {
+            self.handle_request(router, request)
+        };
+        result.and_then(|okay| self.okay_response(okay, get.as_ref())).map(|response| match &get {
+            Some(get) if get.is_not_modified(&response) => not_modified(&response),
+            _ => response,
+        }).unwrap_or_else(|e| {
+            let page = router.render_error(&e);
+            self.error_response(e, page)
+        })
+    }
+
+    /// Handle `request`, which was received at time `received`, and send the
+    /// response.
+    fn respond(&self, router: &mut impl Route, mut request: Request, received: Instant) {
+        // A body longer than the maximum is never read, and the client is not
+        // asked to send it.
+        let is_too_long = request.body_length().is_some_and(|length| length as u64 > self.max_body_length);
+        let mut no_body = std::io::empty();
+        let incoming = Incoming {
+            method: request.method().clone(),
+            url: request.url().into(),
+            headers: request.headers().to_vec(),
+            remote_addr: request.remote_addr().copied(),
+            body: if is_too_long { &mut no_body } else { request.as_reader() },
+        };
+        // Send `Content-Length` whenever it is known, even for large files, so
+        // that clients can show progress, and so that `HEAD` requests work.
+        let response = self.response(router, incoming, received).with_chunked_threshold(usize::MAX);
+        request.respond(response).unwrap_or_else(|e2| (self.log)(&format!("IO Error: {}", e2)));
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Configures a web server.
+///
+/// ```no_run
+/// use petite_http::{ServerBuilder, HttpOkay};
+/// # struct App;
+/// # impl petite_http::Route for App {
+/// #     fn route(&mut self, _: &petite_http::Context, _: &[String], _: impl petite_http::Callback) -> petite_http::Result {
+/// #         Ok(HttpOkay::Empty)
+/// #     }
+/// # }
+/// let server = ServerBuilder::new("127.0.0.1:8082")
+///     .base_url("https://example.com/")
+///     .max_body_length(1 << 20)
+///     .log(|message| eprintln!("{message}"))
+///     .build()?;
+/// let app = server.start(App);
+/// # Ok::<(), petite_http::StartError>(())
+/// ```
+pub struct ServerBuilder {
+    addresses: Vec<String>,
+    base_url: Option<String>,
+    workers: usize,
+    max_body_length: u64,
+    max_uploads: usize,
+    queue_timeout: Option<Duration>,
+    log: Log,
+    error_page: Option<ErrorPage>,
+    handle_signals: bool,
+}
+
+impl ServerBuilder {
+    /// Listen on `server_address`, e.g. "127.0.0.1:8082".
+    pub fn new(server_address: impl Into<String>) -> Self {
+        Self {
+            addresses: vec![server_address.into()],
+            base_url: None,
+            workers: thread::available_parallelism().map_or(1, |n| n.get()),
+            max_body_length: 16 << 20,
+            max_uploads: 20,
+            queue_timeout: None,
+            log: Box::new(|message| println!("{}", message)),
+            error_page: None,
+            handle_signals: true,
+        }
+    }
+
+    /// Also listen on `server_address`.
+    pub fn address(mut self, server_address: impl Into<String>) -> Self {
+        self.addresses.push(server_address.into());
+        self
+    }
+
+    /// The publicly visible URL of this web server. It should end with `/`.
+    /// This is useful for constructing absolute URLs for HTTP redirects.
+    ///
+    /// If the first server address is public, this can be omitted.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// The number of threads used by [`Server::start_pool()`] and
+    /// [`Server::spawn_pool()`]. At least one is used. The default is the
+    /// number of CPUs.
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// The maximum length of a request body, in bytes. Longer requests are
+    /// rejected with [`HttpError::PayloadTooLarge`]. The default is 16MiB.
+    ///
+    /// If a request declares a longer body, the body is not read. Unless it is
+    /// short, the connection is closed after the response is sent.
+    pub fn max_body_length(mut self, max_body_length: u64) -> Self {
+        self.max_body_length = max_body_length;
+        self
+    }
+
+    /// The maximum number of files in a `multipart/form-data` request body.
+    /// Requests with more are rejected with [`HttpError::PayloadTooLarge`].
+    /// The default is 20.
+    ///
+    /// Each file is kept open until the request has been handled.
+    pub fn max_uploads(mut self, max_uploads: usize) -> Self {
+        self.max_uploads = max_uploads;
+        self
+    }
+
+    /// Reject requests that have waited longer than `timeout` to be handled,
+    /// with [`HttpError::ServiceUnavailable`]. By default, requests wait as
+    /// long as necessary.
+    ///
+    /// This sheds load when handlers are slow. It does not limit the time
+    /// taken by clients to send requests, nor by handlers.
+    pub fn queue_timeout(mut self, timeout: Duration) -> Self {
+        self.queue_timeout = Some(timeout);
+        self
+    }
+
+    /// Send log messages to `log` instead of printing them.
+    pub fn log(mut self, log: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.log = Box::new(log);
+        self
+    }
+
+    /// Use `error_page` to generate error responses if the router's
+    /// [`Route::render_error()`] returns `None`.
+    pub fn error_page(
+        mut self,
+        error_page: impl Fn(&HttpError) -> Option<HttpOkay> + Send + Sync + 'static,
+    ) -> Self {
+        self.error_page = Some(Box::new(error_page));
+        self
+    }
+
+    /// Whether to stop the server on SIGINT or SIGTERM. The default is
+    /// `true`.
+    ///
+    /// The signal handler is installed when the first such server starts,
+    /// and stays installed. It stops every such server that is running, or,
+    /// if there are none, exits with the status that reports the signal,
+    /// e.g. 143 for SIGTERM. Pass `false` to handle signals yourself, for
+    /// example by calling [`ServerHandle::shutdown()`].
+    pub fn handle_signals(mut self, handle_signals: bool) -> Self {
+        self.handle_signals = handle_signals;
+        self
+    }
+
+    /// Check the configuration and start listening on the server addresses.
+    pub fn build(self) -> std::result::Result<Server, StartError> {
+        let addresses = self.addresses.clone();
+        let workers = self.workers;
+        let handle_signals = self.handle_signals;
+        let config = self.into_config()?;
+        let listeners = addresses.iter().map(
+            |address| tiny_http::Server::http(address).map_err(StartError::Bind)
+        ).collect::<std::result::Result<_, _>>()?;
+        Ok(Server {config, listeners, workers, handle_signals})
+    }
+
+    /// Check the configuration, without listening.
+    pub(crate) fn into_config(self) -> std::result::Result<Config, StartError> {
+        let server_url = &format!("http://{}/", self.addresses[0]);
+        let base_url = self.base_url.as_deref().unwrap_or(server_url);
+        if !base_url.ends_with('/') { return Err(StartError::BaseUrlWithoutSlash(base_url.into())); }
+        let server_url = Url::parse(server_url).map_err(StartError::InvalidServerUrl)?;
+        let base_url = Url::parse(base_url).map_err(StartError::InvalidBaseUrl)?;
+        Ok(Config {
+            server_url,
+            base_url,
+            max_body_length: self.max_body_length,
+            max_uploads: self.max_uploads,
+            queue_timeout: self.queue_timeout,
+            log: self.log,
+            error_page: self.error_page,
+        })
+    }
+}
+
+/// A web server that is ready to run. Constructed by [`ServerBuilder`].
+pub struct Server {
+    config: Config,
+    listeners: Vec<tiny_http::Server>,
+    workers: usize,
+
+    /// Whether to stop on SIGINT or SIGTERM.
+    handle_signals: bool,
+}
+
+/// Requests waiting to be handled, and when they were received.
+type Queue = Mutex<mpsc::Receiver<(Request, Instant)>>;
+
+impl Server {
+    /// The local URL that the server serves.
+    pub fn server_url(&self) -> &Url { &self.config.server_url }
+
+    /// Run until SIGINT or SIGTERM, then return `router`. If
+    /// [`ServerBuilder::handle_signals()`] is `false`, run forever.
+    ///
+    /// Requests are handled one at a time, on the calling thread.
+    pub fn start<R: Route>(self, mut router: R) -> R {
+        let (running, queue, acceptors) = self.listen(1);
+        running.serve(&mut router, &queue);
+        join_all(acceptors);
+        router
+    }
+
+    /// Run until SIGINT or SIGTERM, handling requests on several threads,
+    /// then return `router`.
+    pub fn start_pool<R: SharedRoute + Sync>(self, router: R) -> R {
+        let workers = self.workers;
+        let (running, queue, acceptors) = self.listen(workers);
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| running.serve(&mut Shared(&router), &queue));
+            }
+        });
+        join_all(acceptors);
+        router
+    }
+
+    /// Like [`Server::start()`], but runs in the background.
+    pub fn spawn<R: Route + Send + 'static>(self, mut router: R) -> ServerHandle<R> {
+        let (running, queue, acceptors) = self.listen(1);
+        let worker = {
+            let running = Arc::clone(&running);
+            thread::spawn(move || {
+                running.serve(&mut router, &queue);
+                Some(router)
+            })
+        };
+        ServerHandle {running, acceptors, workers: vec![worker]}
+    }
+
+    /// Like [`Server::start_pool()`], but runs in the background.
+    pub fn spawn_pool<R: SharedRoute + Send + Sync + 'static>(self, router: R) -> ServerHandle<R> {
+        let workers = self.workers;
+        let (running, queue, acceptors) = self.listen(workers);
+        let queue = Arc::new(queue);
+        let router = Arc::new(router);
+        let workers = (0..workers).map(|_| {
+            let running = Arc::clone(&running);
+            let queue = Arc::clone(&queue);
+            let router = Arc::clone(&router);
+            thread::spawn(move || {
+                running.serve(&mut Shared(&*router), &queue);
+                // The last thread to finish returns the router.
+                Arc::into_inner(router)
+            })
+        }).collect();
+        ServerHandle {running, acceptors, workers}
+    }
+
+    /// Start a thread for each listener to receive requests, and arrange to
+    /// stop on SIGINT or SIGTERM if required.
+    fn listen(self, workers: usize) -> (Arc<Running>, Queue, Vec<JoinHandle<()>>) {
+        for listener in &self.listeners {
+            (self.config.log)(&format!("Listening on http://{}/ with {} threads", listener.server_addr(), workers));
+        }
+        let running = Arc::new(Running {
+            config: self.config,
+            listeners: self.listeners,
+            is_stopping: AtomicBool::new(false),
+        });
+        if self.handle_signals { stop_on_signal(&running); }
+        let (sender, receiver) = mpsc::channel();
+        let acceptors = (0..running.listeners.len()).map(|index| {
+            let running = Arc::clone(&running);
+            let sender = sender.clone();
+            thread::spawn(move || running.accept(index, sender))
+        }).collect();
+        (running, Mutex::new(receiver), acceptors)
+    }
+}
+
+/// Wait for `threads` to finish, propagating any panic.
+fn join_all<T>(threads: Vec<JoinHandle<T>>) -> Vec<T> {
+    threads.into_iter().map(
+        |thread| thread.join().unwrap_or_else(|e| std::panic::resume_unwind(e))
+    ).collect()
+}
+
+/// The state of a running [`Server`], shared by its threads.
+struct Running {
+    config: Config,
+    listeners: Vec<tiny_http::Server>,
+
+    /// Set when the server should stop accepting requests.
+    is_stopping: AtomicBool,
+}
+
+impl Running {
+    /// Stop accepting requests. `serve()` returns after handling the requests
+    /// that have already been received.
+    fn stop(&self) {
+        self.is_stopping.store(true, Ordering::SeqCst);
+        for listener in &self.listeners { listener.unblock(); }
+    }
+
+    /// Pass requests from `listeners[index]` to `queue` until `stop()` is
+    /// called.
+    fn accept(&self, index: usize, queue: mpsc::Sender<(Request, Instant)>) {
+        loop {
+            match self.listeners[index].recv() {
+                Ok(request) => {
+                    // Fails only if every worker has panicked.
+                    if queue.send((request, Instant::now())).is_err() { return; }
                 },
-                Err(HttpError::Invalid) => {
-                    request.respond(Response::from_string("Invalid request").with_status_code(400))
-                },
-                Err(HttpError::NotFound) => {
-                    request.respond(Response::from_string("Not found").with_status_code(404))
-                },
-                Err(HttpError::Error(e)) => {
-                    println!("Error: {}", e);
-                    request.respond(Response::from_string("Server error").with_status_code(500))
-                },
-            }.unwrap_or_else(|e2| println!("IO Error: {}", e2));
+                Err(_) if self.is_stopping.load(Ordering::SeqCst) => { return; },
+                Err(e) => { (self.config.log)(&format!("IO Error: {}", e)); },
+            }
+        }
+    }
+
+    /// Handle requests until `stop()` is called.
+    fn serve(&self, router: &mut impl Route, queue: &Queue) {
+        loop {
+            let next = queue.lock().unwrap_or_else(PoisonError::into_inner).recv();
+            // `recv()` fails when all the acceptors have stopped.
+            let Ok((request, received)) = next else { return; };
+            self.config.respond(router, request, received);
         }
-        unreachable!();
     }
 }
 
-/// Run for ever!
+/// Servers to stop on SIGINT or SIGTERM.
+static SERVERS: Mutex<Vec<Weak<Running>>> = Mutex::new(Vec::new());
+
+/// Arrange for `server` to be stopped on SIGINT or SIGTERM.
+///
+/// If no server is running, these signals exit the process, as usual.
+fn stop_on_signal(server: &Arc<Running>) {
+    static INSTALL_HANDLER: Once = Once::new();
+    INSTALL_HANDLER.call_once(|| {
+        set_signal_handler(|exit_code| {
+            let mut is_running = false;
+            for server in SERVERS.lock().unwrap_or_else(PoisonError::into_inner).drain(..) {
+                if let Some(server) = server.upgrade() {
+                    (server.config.log)(&format!("Shutting down {}", server.config.server_url));
+                    server.stop();
+                    is_running = true;
+                }
+            }
+            if !is_running { std::process::exit(exit_code); }
+        }).unwrap_or_else(|e| (server.config.log)(&format!("Could not handle signals: {}", e)));
+    });
+    let mut servers = SERVERS.lock().unwrap_or_else(PoisonError::into_inner);
+    servers.retain(|server| server.strong_count() > 0);
+    servers.push(Arc::downgrade(server));
+}
+
+/// Call `handler` on a background thread whenever SIGINT or SIGTERM is
+/// received. It is passed the exit status that reports the signal.
+///
+/// SIGHUP is left alone, so that `nohup` works.
+#[cfg(unix)]
+fn set_signal_handler(handler: impl Fn(i32) + Send + 'static) -> std::io::Result<()> {
+    use signal_hook::consts::{SIGINT, SIGTERM};
+    let mut signals = signal_hook::iterator::Signals::new([SIGINT, SIGTERM])?;
+    thread::spawn(move || for signal in signals.forever() { handler(128 + signal); });
+    Ok(())
+}
+
+/// Call `handler` on a background thread whenever Ctrl-C is pressed or the
+/// console is closed. It is passed the exit status that reports this.
+#[cfg(not(unix))]
+fn set_signal_handler(handler: impl Fn(i32) + Send + 'static) -> std::io::Result<()> {
+    ctrlc::set_handler(move || handler(130)).map_err(std::io::Error::other)
+}
+
+/// A web server running in the background. Returned by [`Server::spawn()`]
+/// and [`Server::spawn_pool()`].
+pub struct ServerHandle<R> {
+    running: Arc<Running>,
+
+    /// The threads that receive requests.
+    acceptors: Vec<JoinHandle<()>>,
+
+    /// The threads that handle requests. One of them will return the router.
+    workers: Vec<JoinHandle<Option<R>>>,
+}
+
+impl<R> ServerHandle<R> {
+    /// The local URL that the server serves.
+    pub fn server_url(&self) -> &Url { &self.running.config.server_url }
+
+    /// Stop accepting requests, wait for the requests that have already been
+    /// received to be handled, and return the router.
+    pub fn shutdown(self) -> R {
+        self.running.stop();
+        self.join()
+    }
+
+    /// Wait until the server stops, e.g. on SIGINT or SIGTERM, and return the
+    /// router.
+    pub fn join(self) -> R {
+        join_all(self.acceptors);
+        join_all(self.workers).into_iter().flatten().next().expect("No thread returned the router")
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Construct a [`ServerBuilder`] with the arguments of [`start()`].
+fn builder(server_address: String, base_url: Option<String>) -> ServerBuilder {
+    let builder = ServerBuilder::new(server_address);
+    match base_url {
+        Some(base_url) => builder.base_url(base_url),
+        None => builder,
+    }
+}
+
+/// Run until SIGINT or SIGTERM, then return `router`.
+///
+/// Fails if the server cannot be started.
 ///
 /// - server_address - E.g. "127.0.0.1:8082".
 /// - base_url - The publicly visible URL of this web server, if any. It should
 ///   end with `/`. This is useful for constructing absolute URLs for HTTP redirects.
 ///   If `server_address` is public, `base_url` can be omitted.
-/// - handler - Defines the web application.
-pub fn start(server_address: String, base_url: Option<String>, router: impl Route) -> ! {
-    let server = Server::new(&server_address, base_url.as_ref().map(AsRef::as_ref));
-    println!("Listening on {}", server.server_url);
-    server.handle_requests(router);
+/// - router - Defines the web application.
+///
+/// Requests are handled one at a time, on the calling thread. Use
+/// [`ServerBuilder`] for more options.
+pub fn start<R: Route>(
+    server_address: String,
+    base_url: Option<String>,
+    router: R,
+) -> std::result::Result<R, StartError> {
+    Ok(builder(server_address, base_url).build()?.start(router))
+}
+
+/// Run until SIGINT or SIGTERM, handling up to `threads` requests at a time,
+/// then return `router`.
+///
+/// - server_address, base_url - As for [`start()`].
+/// - threads - The number of worker threads. At least one is used.
+/// - router - Defines the web application. It must be safe to share between
+///   threads.
+pub fn start_pool<R: SharedRoute + Sync>(
+    server_address: String,
+    base_url: Option<String>,
+    threads: usize,
+    router: R,
+) -> std::result::Result<R, StartError> {
+    Ok(builder(server_address, base_url).workers(threads).build()?.start_pool(router))
+}
+
+/// Like [`start()`], but runs in the background.
+pub fn spawn<R: Route + Send + 'static>(
+    server_address: String,
+    base_url: Option<String>,
+    router: R,
+) -> std::result::Result<ServerHandle<R>, StartError> {
+    Ok(builder(server_address, base_url).build()?.spawn(router))
+}
+
+/// Like [`start_pool()`], but runs in the background.
+pub fn spawn_pool<R: SharedRoute + Send + Sync + 'static>(
+    server_address: String,
+    base_url: Option<String>,
+    threads: usize,
+    router: R,
+) -> std::result::Result<ServerHandle<R>, StartError> {
+    Ok(builder(server_address, base_url).workers(threads).build()?.spawn_pool(router))
 }
@@ -0,0 +1,94 @@
+//! Serve the files in a directory.
+
+use std::io::{ErrorKind};
+use std::path::{Component, Path, PathBuf};
+
+use super::{validate_name, Callback, Context, HttpError, HttpOkay, Method, Result, Route};
+
+/// Decides whether a path segment may be served.
+type Filter = Box<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// A [`Route`] that serves the files under a root directory. The URL path is
+/// the path of the file relative to the root directory.
+///
+/// To serve a directory as part of a larger web application, pass the rest
+/// of the path to it:
+///
+/// ```
+/// use petite_http::{Callback, Context, HttpError, Result, Route, StaticDir};
+///
+/// struct App {
+///     assets: StaticDir,
+/// }
+///
+/// impl Route for App {
+///     fn route(&mut self, context: &Context, path: &[String], callback: impl Callback) -> Result {
+///         match path.first().map(String::as_str) {
+///             Some("assets") => self.assets.route(context, &path[1..], callback),
+///             _ => Err(HttpError::NotFound),
+///         }
+///     }
+/// }
+///
+/// let app = App {assets: StaticDir::new("assets")};
+/// ```
+///
+/// Only files are served, not directories. Only `GET` and `HEAD` requests are
+/// allowed, and `OPTIONS` requests are answered. The `Content-Type` is
+/// inferred as for [`HttpOkay::from_path()`].
+pub struct StaticDir {
+    root: PathBuf,
+    filter: Filter,
+}
+
+impl StaticDir {
+    /// Serve the files under `root`.
+    ///
+    /// By default, each path segment must pass [`validate_name()`] and must
+    /// not start with `.`, so hidden files are not served.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            filter: Box::new(|segment| !segment.starts_with('.') && validate_name(segment.as_ref()).is_ok()),
+        }
+    }
+
+    /// Replace the policy that decides whether a path segment may be
+    /// served. `filter` cannot allow `.`, `..`, or segments that contain a
+    /// path separator.
+    pub fn filter(self, filter: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        Self {filter: Box::new(filter), ..self}
+    }
+
+    /// Find the file named by `path`.
+    ///
+    /// Fails with [`HttpError::NotFound`] if any segment of `path` is
+    /// rejected, if the file does not exist, if it is not a file, or if it is
+    /// outside the root directory, e.g. because of a symbolic link.
+    pub fn resolve(&self, path: &[String]) -> std::result::Result<PathBuf, HttpError> {
+        let root = self.root.canonicalize()?;
+        let mut file_path = root.clone();
+        for segment in path {
+            let mut components = Path::new(segment).components();
+            let is_normal = matches!(components.next(), Some(Component::Normal(c)) if c == segment.as_str())
+                && components.next().is_none();
+            if !is_normal || segment.contains(['/', '\\', '\0']) || !(self.filter)(segment) {
+                return Err(HttpError::NotFound);
+            }
+            file_path.push(segment);
+        }
+        let file_path = file_path.canonicalize().map_err(|e| match e.kind() {
+            ErrorKind::NotFound | ErrorKind::NotADirectory => HttpError::NotFound,
+            _ => e.into(),
+        })?;
+        if !file_path.starts_with(&root) || !file_path.is_file() { return Err(HttpError::NotFound); }
+        Ok(file_path)
+    }
+}
+
+impl Route for StaticDir {
+    fn route(&mut self, context: &Context, path: &[String], _callback: impl Callback) -> Result {
+        if let Some(response) = context.check_method(&[Method::Get, Method::Head])? { return Ok(response); }
+        Ok(HttpOkay::from_path(self.resolve(path)?)?)
+    }
+}
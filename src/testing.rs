@@ -0,0 +1,197 @@
+//! Exercise a [`Route`] in-process, without a socket.
+//!
+//! ```
+//! use petite_http::{Context, Handle, HttpOkay, Result, html};
+//! use petite_http::testing::{TestClient};
+//!
+//! struct Hello;
+//!
+//! impl Handle for Hello {
+//!     type Params = Vec<(String, String)>;
+//!
+//!     fn handle_get(&mut self, _context: &Context, path: &[String], _params: Self::Params) -> Result {
+//!         Ok(HttpOkay::Html(Box::new(format!("Hello, {}!", path.join("/")))))
+//!     }
+//! }
+//!
+//! let mut client = TestClient::new(Hello);
+//! let response = client.get("/<world>");
+//! assert_eq!(response.status, 200);
+//! assert_eq!(response.header("Content-Type"), Some("text/html; charset=utf-8"));
+//! assert_eq!(response.text(), "Hello, &lt;world&gt;!");
+//! ```
+
+use std::borrow::{Cow};
+use std::io::{Cursor, Read};
+use std::net::{SocketAddr};
+use std::time::{Instant};
+
+use tiny_http::{Header, Method};
+
+use super::{Route, ServerBuilder, StartError};
+use super::server::{Config, Incoming};
+
+/// A synthetic HTTP request, to be sent with [`TestClient::send()`].
+#[derive(Debug, Clone)]
+pub struct TestRequest {
+    method: Method,
+    url: String,
+    headers: Vec<(String, String)>,
+    remote_addr: Option<SocketAddr>,
+    body: Vec<u8>,
+}
+
+impl TestRequest {
+    /// - method - The HTTP method.
+    /// - url - The request target, e.g. `/foo?bar=baz`.
+    pub fn new(method: Method, url: impl Into<String>) -> Self {
+        Self {method, url: url.into(), headers: Vec::new(), remote_addr: None, body: Vec::new()}
+    }
+
+    /// A `GET` request.
+    pub fn get(url: impl Into<String>) -> Self { Self::new(Method::Get, url) }
+
+    /// A `HEAD` request.
+    pub fn head(url: impl Into<String>) -> Self { Self::new(Method::Head, url) }
+
+    /// A `POST` request.
+    pub fn post(url: impl Into<String>) -> Self { Self::new(Method::Post, url) }
+
+    /// A `PUT` request.
+    pub fn put(url: impl Into<String>) -> Self { Self::new(Method::Put, url) }
+
+    /// A `PATCH` request.
+    pub fn patch(url: impl Into<String>) -> Self { Self::new(Method::Patch, url) }
+
+    /// A `DELETE` request.
+    pub fn delete(url: impl Into<String>) -> Self { Self::new(Method::Delete, url) }
+
+    /// Add a request header.
+    pub fn header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((key.into(), value.into()));
+        self
+    }
+
+    /// Set the request body and its `Content-Type`.
+    ///
+    /// Unless a `Content-Length` header is added, it is the length of `body`.
+    /// As with a real server, the router cannot read past the
+    /// `Content-Length`.
+    pub fn body(self, content_type: impl Into<String>, body: impl Into<Vec<u8>>) -> Self {
+        Self {body: body.into(), ..self.header("Content-Type", content_type)}
+    }
+
+    /// Set the request body to an `application/x-www-form-urlencoded` form.
+    pub fn form(self, params: &[(&str, &str)]) -> Self {
+        let body = url::form_urlencoded::Serializer::new(String::new()).extend_pairs(params).finish();
+        self.body("application/x-www-form-urlencoded", body)
+    }
+
+    /// Set the address of the client. By default there is none.
+    pub fn remote_addr(self, remote_addr: SocketAddr) -> Self {
+        Self {remote_addr: Some(remote_addr), ..self}
+    }
+}
+
+/// The response to a [`TestRequest`].
+#[derive(Debug, Clone)]
+pub struct TestResponse {
+    /// The HTTP status code.
+    pub status: u16,
+
+    /// The names and values of the response headers, including
+    /// `Content-Length` if the server would send it.
+    pub headers: Vec<(String, String)>,
+
+    /// The response body.
+    pub body: Vec<u8>,
+}
+
+impl TestResponse {
+    /// The value of the first header called `name`, which is case-insensitive.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+    }
+
+    /// The response body, decoded as UTF-8.
+    pub fn text(&self) -> Cow<'_, str> { String::from_utf8_lossy(&self.body) }
+}
+
+/// Sends synthetic requests to a [`Route`], handling them exactly as a
+/// server would.
+pub struct TestClient<R> {
+    config: Config,
+    router: R,
+}
+
+impl<R: Route> TestClient<R> {
+    /// A client for a server at `http://localhost/`, with default settings,
+    /// that does not log anything.
+    pub fn new(router: R) -> Self {
+        let builder = ServerBuilder::new("localhost").log(|_| {});
+        Self::with_builder(builder, router).unwrap() // The configuration is valid.
+    }
+
+    /// A client for a server configured by `builder`. The server addresses
+    /// are not bound, and the number of workers is ignored.
+    pub fn with_builder(builder: ServerBuilder, router: R) -> Result<Self, StartError> {
+        Ok(Self {config: builder.into_config()?, router})
+    }
+
+    /// The router.
+    pub fn router(&mut self) -> &mut R { &mut self.router }
+
+    /// Discard the client and return the router.
+    pub fn into_router(self) -> R { self.router }
+
+    /// Send a `GET` request for `url`, e.g. `/foo?bar=baz`.
+    pub fn get(&mut self, url: &str) -> TestResponse {
+        self.send(TestRequest::get(url))
+    }
+
+    /// Send a `POST` request for `url` with an
+    /// `application/x-www-form-urlencoded` body.
+    pub fn post_form(&mut self, url: &str, params: &[(&str, &str)]) -> TestResponse {
+        self.send(TestRequest::post(url).form(params))
+    }
+
+    /// Send `request` and return the response.
+    ///
+    /// Panics if a request header is invalid, or if the response body cannot
+    /// be read.
+    pub fn send(&mut self, request: TestRequest) -> TestResponse {
+        let mut headers: Vec<Header> = request.headers.iter().map(
+            |(key, value)| Header::from_bytes(key.as_bytes(), value.as_bytes()).unwrap_or_else(
+                |()| panic!("Invalid HTTP header {key:?}: {value:?}")
+            )
+        ).collect();
+        if !request.body.is_empty() && !headers.iter().any(|h| h.field.equiv("Content-Length")) {
+            headers.push(Header::from_bytes(&b"Content-Length"[..], request.body.len().to_string()).unwrap());
+        }
+        // Like tiny_http, read no more than the declared length.
+        let length = headers.iter().find(|h| h.field.equiv("Content-Length")).and_then(
+            |h| h.value.as_str().parse().ok()
+        ).unwrap_or(0);
+        let is_head = Method::Head == request.method;
+        let incoming = Incoming {
+            method: request.method,
+            url: request.url,
+            headers,
+            remote_addr: request.remote_addr,
+            body: &mut Cursor::new(request.body).take(length),
+        };
+        let response = self.config.response(&mut self.router, incoming, Instant::now());
+        let status = response.status_code().0;
+        let mut headers: Vec<(String, String)> = response.headers().iter().map(
+            |h| (h.field.to_string(), h.value.to_string())
+        ).collect();
+        if let Some(length) = response.data_length() {
+            headers.push(("Content-Length".into(), length.to_string()));
+        }
+        let mut body = Vec::new();
+        if !is_head {
+            response.into_reader().read_to_end(&mut body).expect("Could not read the response body");
+        }
+        TestResponse {status, headers, body}
+    }
+}
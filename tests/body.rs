@@ -0,0 +1,108 @@
+//! Request bodies and their maximum length.
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration};
+
+use petite_http::{Body, Context, Handle, HttpOkay, Method, Result, ServerBuilder};
+use petite_http::testing::{TestClient, TestRequest};
+
+/// Echoes the body of `PUT` requests.
+struct Echo;
+
+impl Handle for Echo {
+    type Params = Vec<(String, String)>;
+
+    const METHODS: &'static [Method] = &[Method::Put];
+
+    fn handle_get(&mut self, _context: &Context, _path: &[String], _params: Self::Params) -> Result {
+        Ok(HttpOkay::Empty)
+    }
+
+    fn handle_put(&mut self, _context: &Context, path: &[String], _params: Self::Params, body: &mut Body) -> Result {
+        if let Some(limit) = path.first() { body.limit(limit.parse().unwrap())?; }
+        Ok(HttpOkay::Html(Box::new(body.text()?)))
+    }
+}
+
+fn client() -> TestClient<Echo> {
+    TestClient::with_builder(ServerBuilder::new("localhost").max_body_length(10).log(|_| {}), Echo).unwrap()
+}
+
+#[test]
+fn body_within_maximum() {
+    let response = client().send(TestRequest::put("/").body("text/plain", "0123456789"));
+    assert_eq!(response.status, 200);
+    assert_eq!(response.text(), "0123456789");
+}
+
+#[test]
+fn declared_length_over_maximum() {
+    let response = client().send(TestRequest::put("/").body("text/plain", "0123456789A"));
+    assert_eq!(response.status, 413);
+}
+
+#[test]
+fn body_over_declared_length() {
+    // The rest of the body would be the next request.
+    let request = TestRequest::put("/").header("Content-Length", "5").body("text/plain", "0123456789A");
+    let response = client().send(request);
+    assert_eq!(response.status, 200);
+    assert_eq!(response.text(), "01234");
+}
+
+#[test]
+fn body_over_lowered_limit() {
+    let mut client = client();
+    assert_eq!(client.send(TestRequest::put("/5").body("text/plain", "01234")).status, 200);
+    assert_eq!(client.send(TestRequest::put("/5").body("text/plain", "012345")).status, 413);
+}
+
+/// Send `request` to a real server and return the response headers.
+fn send_over_socket(address: &str, request: &[u8]) -> String {
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream.write_all(request).unwrap();
+    let mut response = Vec::new();
+    let mut buf = [0; 1024];
+    while !response.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut buf).unwrap();
+        assert!(n > 0, "Connection closed before the response headers: {:?}", String::from_utf8_lossy(&response));
+        response.extend_from_slice(&buf[..n]);
+    }
+    String::from_utf8(response).unwrap()
+}
+
+/// The number of threads and open file descriptors of this process.
+#[cfg(target_os = "linux")]
+fn resource_usage() -> (usize, usize) {
+    let count = |dir| std::fs::read_dir(dir).unwrap().count();
+    (count("/proc/self/task"), count("/proc/self/fd"))
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn huge_declared_length_over_socket() {
+    let address = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+    let server = ServerBuilder::new(&address).max_body_length(10).log(|_| {}).build().unwrap().spawn(Echo);
+    let mut before = (0, 0);
+    for i in 0..50 {
+        // Measure after the thread pools have started.
+        if 10 == i { before = resource_usage(); }
+        let mut stream = TcpStream::connect(&address).unwrap();
+        stream.write_all(b"PUT / HTTP/1.1\r\nHost: localhost\r\nContent-Length: 50000000000\r\n\r\nabc").unwrap();
+        // The server closes the connection after the response, without
+        // reading the body.
+        stream.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 413 "), "{response}");
+    }
+    thread::sleep(Duration::from_millis(200));
+    let after = resource_usage();
+    assert!(after.0 < before.0 + 10 && after.1 < before.1 + 10, "Threads and files before: {before:?}, after: {after:?}");
+    // The server is still running.
+    let response = send_over_socket(&address, b"PUT / HTTP/1.1\r\nHost: localhost\r\nContent-Length: 5\r\n\r\nhello");
+    assert!(response.starts_with("HTTP/1.1 200 "), "{response}");
+    server.shutdown();
+}
@@ -0,0 +1,64 @@
+//! Error responses and custom error pages.
+
+use petite_http::{Callback, Context, HttpError, HttpOkay, Method, Result, Route, ServerBuilder};
+use petite_http::testing::{TestClient};
+
+/// Fails with the error named by the path, and renders a page for 404 only.
+struct Site;
+
+impl Route for Site {
+    fn route(&mut self, _context: &Context, path: &[String], _callback: impl Callback) -> Result {
+        match path.first().map(String::as_str) {
+            Some("invalid") => Err(HttpError::Invalid("Bad name".into())),
+            Some("put") => Err(HttpError::MethodNotAllowed(&[Method::Put])),
+            Some("gone") => Err(HttpError::Gone),
+            _ => Err(HttpError::NotFound),
+        }
+    }
+
+    fn render_error(&mut self, error: &HttpError) -> Option<HttpOkay> {
+        match error {
+            HttpError::NotFound => Some(HttpOkay::Html(Box::new("No such page"))),
+            HttpError::MethodNotAllowed(_) => Some(HttpOkay::Html(Box::new("Wrong method"))),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn default_messages() {
+    let mut client = TestClient::new(Site);
+    let response = client.get("/invalid");
+    assert_eq!(response.status, 400);
+    assert_eq!(response.text(), "Invalid request: Bad name");
+    let response = client.get("/gone");
+    assert_eq!(response.status, 410);
+    assert_eq!(response.text(), "Gone");
+}
+
+#[test]
+fn render_error() {
+    let mut client = TestClient::new(Site);
+    let response = client.get("/missing");
+    assert_eq!(response.status, 404);
+    assert_eq!(response.header("Content-Type"), Some("text/html; charset=utf-8"));
+    assert_eq!(response.text(), "No such page");
+    // The headers implied by the error are added to the page.
+    let response = client.get("/put");
+    assert_eq!(response.status, 405);
+    assert_eq!(response.header("Allow"), Some("PUT, OPTIONS"));
+    assert_eq!(response.text(), "Wrong method");
+}
+
+#[test]
+fn error_page() {
+    let builder = ServerBuilder::new("localhost").log(|_| {}).error_page(
+        |error| Some(HttpOkay::Html(Box::new(format!("Fallback for {:?}", error))))
+    );
+    let mut client = TestClient::with_builder(builder, Site).unwrap();
+    // The router's page takes precedence.
+    assert_eq!(client.get("/missing").text(), "No such page");
+    let response = client.get("/gone");
+    assert_eq!(response.status, 410);
+    assert_eq!(response.text(), "Fallback for Gone");
+}
@@ -0,0 +1,167 @@
+//! File responses, which can be conditional or partial.
+
+use std::io::{Write};
+
+use petite_http::{Context, Handle, HttpOkay, Result};
+use petite_http::testing::{TestClient, TestRequest, TestResponse};
+use tempfile::{NamedTempFile};
+
+/// The contents of the file.
+const CONTENTS: &str = "0123456789abcdefghij";
+
+/// Serves a text file.
+struct Serve(NamedTempFile);
+
+impl Handle for Serve {
+    type Params = Vec<(String, String)>;
+
+    fn handle_get(&mut self, _context: &Context, _path: &[String], _params: Self::Params) -> Result {
+        Ok(HttpOkay::from_path(self.0.path())?)
+    }
+}
+
+fn client() -> TestClient<Serve> {
+    let mut file = tempfile::Builder::new().suffix(".txt").tempfile().unwrap();
+    file.write_all(CONTENTS.as_bytes()).unwrap();
+    TestClient::new(Serve(file))
+}
+
+/// Send a `GET` request for the file with the header `key: value`.
+fn get_with(client: &mut TestClient<Serve>, key: &str, value: &str) -> TestResponse {
+    client.send(TestRequest::get("/").header(key, value))
+}
+
+#[test]
+fn validators() {
+    let response = client().get("/");
+    assert_eq!(response.status, 200);
+    assert_eq!(response.text(), CONTENTS);
+    assert!(response.header("ETag").unwrap().starts_with("W/\""));
+    assert!(response.header("Last-Modified").is_some());
+}
+
+#[test]
+fn if_none_match() {
+    let mut client = client();
+    let etag = client.get("/").header("ETag").unwrap().to_owned();
+    let response = get_with(&mut client, "If-None-Match", &etag);
+    assert_eq!(response.status, 304);
+    assert_eq!(response.header("ETag"), Some(etag.as_str()));
+    assert_eq!(response.text(), "");
+    // Weak comparison ignores the `W/` prefix.
+    assert_eq!(get_with(&mut client, "If-None-Match", &etag[2..]).status, 304);
+    assert_eq!(get_with(&mut client, "If-None-Match", "*").status, 304);
+    assert_eq!(get_with(&mut client, "If-None-Match", "\"other\"").status, 200);
+}
+
+#[test]
+fn if_modified_since() {
+    let mut client = client();
+    let last_modified = client.get("/").header("Last-Modified").unwrap().to_owned();
+    assert_eq!(get_with(&mut client, "If-Modified-Since", &last_modified).status, 304);
+    assert_eq!(get_with(&mut client, "If-Modified-Since", "Thu, 01 Jan 2004 00:00:00 GMT").status, 200);
+    // `If-None-Match` takes precedence.
+    let response = client.send(
+        TestRequest::get("/").header("If-None-Match", "\"other\"").header("If-Modified-Since", &last_modified)
+    );
+    assert_eq!(response.status, 200);
+}
+
+#[test]
+fn single_range() {
+    let mut client = client();
+    let response = get_with(&mut client, "Range", "bytes=2-5");
+    assert_eq!(response.status, 206);
+    assert_eq!(response.header("Content-Range"), Some("bytes 2-5/20"));
+    assert_eq!(response.text(), "2345");
+    let response = get_with(&mut client, "Range", "bytes=15-");
+    assert_eq!(response.header("Content-Range"), Some("bytes 15-19/20"));
+    assert_eq!(response.text(), "fghij");
+    // The last position is limited to the end of the file.
+    assert_eq!(get_with(&mut client, "Range", "bytes=18-99").text(), "ij");
+}
+
+#[test]
+fn suffix_range() {
+    let response = get_with(&mut client(), "Range", "bytes=-3");
+    assert_eq!(response.status, 206);
+    assert_eq!(response.header("Content-Range"), Some("bytes 17-19/20"));
+    assert_eq!(response.text(), "hij");
+}
+
+#[test]
+fn multiple_ranges() {
+    let response = get_with(&mut client(), "Range", "bytes=0-1, -2");
+    assert_eq!(response.status, 206);
+    let content_type = response.header("Content-Type").unwrap();
+    let boundary = content_type.strip_prefix("multipart/byteranges; boundary=").unwrap();
+    let expected = format!(
+        "\r\n--{0}\r\nContent-Type: text/plain\r\nContent-Range: bytes 0-1/20\r\n\r\n01\
+        \r\n--{0}\r\nContent-Type: text/plain\r\nContent-Range: bytes 18-19/20\r\n\r\nij\
+        \r\n--{0}--\r\n",
+        boundary,
+    );
+    assert_eq!(response.text(), expected);
+}
+
+#[test]
+fn overlapping_ranges() {
+    // Overlapping and adjacent ranges are merged.
+    let response = get_with(&mut client(), "Range", "bytes=0-3, 2-5, 6-7");
+    assert_eq!(response.status, 206);
+    assert_eq!(response.header("Content-Range"), Some("bytes 0-7/20"));
+    assert_eq!(response.text(), "01234567");
+}
+
+#[test]
+fn range_not_satisfiable() {
+    let mut client = client();
+    let response = get_with(&mut client, "Range", "bytes=20-");
+    assert_eq!(response.status, 416);
+    assert_eq!(response.header("Content-Range"), Some("bytes */20"));
+    assert_eq!(get_with(&mut client, "Range", "bytes=-0").status, 416);
+}
+
+#[test]
+fn invalid_range() {
+    // A `Range` header that is not understood is ignored.
+    let mut client = client();
+    for range in ["lines=1-2", "bytes=5-2", "bytes=x-"] {
+        let response = get_with(&mut client, "Range", range);
+        assert_eq!(response.status, 200, "{}", range);
+        assert_eq!(response.text(), CONTENTS);
+    }
+}
+
+#[test]
+fn if_range() {
+    let mut client = client();
+    let response = client.get("/");
+    let etag = response.header("ETag").unwrap().to_owned();
+    let last_modified = response.header("Last-Modified").unwrap().to_owned();
+    let get = |client: &mut TestClient<Serve>, if_range: &str| client.send(
+        TestRequest::get("/").header("Range", "bytes=0-1").header("If-Range", if_range)
+    );
+    assert_eq!(get(&mut client, &last_modified).status, 206);
+    assert_eq!(get(&mut client, "Thu, 01 Jan 2004 00:00:00 GMT").status, 200);
+    // The `ETag` is weak, so it cannot be used with `If-Range`.
+    assert_eq!(get(&mut client, &etag).status, 200);
+    assert_eq!(get(&mut client, &etag[2..]).status, 200);
+}
+
+#[test]
+fn head() {
+    let mut client = client();
+    let response = client.send(TestRequest::head("/"));
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Content-Length"), Some("20"));
+    assert!(response.header("ETag").is_some());
+    assert_eq!(response.text(), "");
+    // `Range` is ignored, but conditional headers are not.
+    let response = client.send(TestRequest::head("/").header("Range", "bytes=2-5"));
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Content-Length"), Some("20"));
+    assert_eq!(response.header("Content-Range"), None);
+    let etag = response.header("ETag").unwrap();
+    assert_eq!(client.send(TestRequest::head("/").header("If-None-Match", etag)).status, 304);
+}
@@ -0,0 +1,63 @@
+//! `POST` requests with `application/x-www-form-urlencoded` bodies.
+
+use petite_http::{Context, Handle, HttpOkay, Method, Result, Upload};
+use petite_http::testing::{TestClient, TestRequest};
+
+/// Describes the form fields of `POST` requests.
+struct Form;
+
+impl Handle for Form {
+    type Params = Vec<(String, String)>;
+
+    const METHODS: &'static [Method] = &[Method::Get, Method::Head, Method::Post];
+
+    fn handle_get(&mut self, _context: &Context, _path: &[String], _params: Self::Params) -> Result {
+        Ok(HttpOkay::Html(Box::new("form")))
+    }
+
+    fn handle_post(&mut self, _context: &Context, _path: &[String], params: Self::Params, uploads: Vec<Upload>) -> Result {
+        assert!(uploads.is_empty());
+        let text: Vec<String> = params.into_iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+        Ok(HttpOkay::Html(Box::new(text.join(" "))))
+    }
+}
+
+#[test]
+fn post_form() {
+    let mut client = TestClient::new(Form);
+    let response = client.post_form("/", &[("name", "Ann Lee"), ("note", "a&b=c"), ("name", "Bo")]);
+    assert_eq!(response.status, 200);
+    assert_eq!(response.text(), "name=Ann Lee note=a&amp;b=c name=Bo");
+}
+
+#[test]
+fn query_is_not_form() {
+    // The query string is ignored for `POST` requests.
+    let response = TestClient::new(Form).post_form("/?name=Cy", &[("note", "x")]);
+    assert_eq!(response.text(), "note=x");
+}
+
+#[test]
+fn unsupported_media_type() {
+    let mut client = TestClient::new(Form);
+    let response = client.send(TestRequest::post("/").body("text/plain", "name=Ann"));
+    assert_eq!(response.status, 415);
+    // A missing `Content-Type` is also unsupported.
+    assert_eq!(client.send(TestRequest::post("/")).status, 415);
+}
+
+#[test]
+fn post_not_allowed() {
+    struct ReadOnly;
+
+    impl Handle for ReadOnly {
+        type Params = Vec<(String, String)>;
+
+        fn handle_get(&mut self, _context: &Context, _path: &[String], _params: Self::Params) -> Result {
+            Ok(HttpOkay::Empty)
+        }
+    }
+
+    let response = TestClient::new(ReadOnly).post_form("/", &[("name", "Ann")]);
+    assert_eq!(response.status, 405);
+}
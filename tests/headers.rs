@@ -0,0 +1,36 @@
+//! Response headers added by handlers.
+
+use petite_http::{Context, Handle, HttpOkay, Result};
+use petite_http::testing::{TestClient};
+
+/// Adds the header named by the path, with value `5`.
+struct AddHeader;
+
+impl Handle for AddHeader {
+    type Params = Vec<(String, String)>;
+
+    fn handle_get(&mut self, _context: &Context, path: &[String], _params: Self::Params) -> Result {
+        let name = match path.first().map(String::as_str) {
+            Some("connection") => "Connection",
+            Some("content-length") => "content-length",
+            Some("transfer-encoding") => "Transfer-Encoding",
+            _ => "X-Count",
+        };
+        Ok(HttpOkay::Html(Box::new("hello")).with_header(name, "5"))
+    }
+}
+
+#[test]
+fn custom_header() {
+    let response = TestClient::new(AddHeader).get("/");
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("X-Count"), Some("5"));
+}
+
+#[test]
+fn reserved_headers() {
+    let mut client = TestClient::new(AddHeader);
+    for path in ["/connection", "/content-length", "/transfer-encoding"] {
+        assert_eq!(client.get(path).status, 500, "{}", path);
+    }
+}
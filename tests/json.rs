@@ -0,0 +1,64 @@
+//! JSON request and response bodies. Requires the `json` feature.
+
+#![cfg(feature = "json")]
+
+use petite_http::{Body, Context, Handle, HttpOkay, Method, Result};
+use petite_http::testing::{TestClient, TestRequest};
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+/// Reflects the `Point` in the body of `PUT` requests.
+struct Reflect;
+
+impl Handle for Reflect {
+    type Params = Vec<(String, String)>;
+
+    const METHODS: &'static [Method] = &[Method::Get, Method::Head, Method::Put];
+
+    fn handle_get(&mut self, _context: &Context, _path: &[String], _params: Self::Params) -> Result {
+        HttpOkay::json(&Point {x: 1, y: 2})
+    }
+
+    fn handle_put(&mut self, _context: &Context, _path: &[String], _params: Self::Params, body: &mut Body) -> Result {
+        let point: Point = body.json()?;
+        HttpOkay::json(&Point {x: point.y, y: point.x})
+    }
+}
+
+/// A `PUT` request with a JSON `body`.
+fn put(body: &str) -> TestRequest {
+    TestRequest::put("/").body("application/json", body)
+}
+
+#[test]
+fn response() {
+    let response = TestClient::new(Reflect).get("/");
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Content-Type"), Some("application/json"));
+    assert_eq!(response.text(), r#"{"x":1,"y":2}"#);
+}
+
+#[test]
+fn request() {
+    let response = TestClient::new(Reflect).send(put(r#"{"x": 3, "y": -4}"#));
+    assert_eq!(response.status, 200);
+    assert_eq!(response.text(), r#"{"x":-4,"y":3}"#);
+}
+
+#[test]
+fn invalid() {
+    let mut client = TestClient::new(Reflect);
+    for (body, message) in [
+        ("{\"x\": 3", "Invalid request: Invalid JSON: EOF while parsing an object at line 1 column 7"),
+        ("{\"x\": 3}", "Invalid request: Invalid JSON: missing field `y` at line 1 column 8"),
+        ("{\"x\": \"3\", \"y\": 4}", "Invalid request: Invalid JSON: invalid type: string \"3\", expected i32 at line 1 column 9"),
+    ] {
+        let response = client.send(put(body));
+        assert_eq!(response.status, 400, "{}", body);
+        assert_eq!(response.text(), message);
+    }
+}
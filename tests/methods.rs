@@ -0,0 +1,48 @@
+//! `OPTIONS` requests and `405 Method Not Allowed`.
+
+use petite_http::{Callback, Context, Handle, HttpOkay, Method, Result, Route};
+use petite_http::testing::{TestClient, TestRequest};
+
+/// Allows `GET`, `HEAD` and `POST`.
+struct Page;
+
+impl Handle for Page {
+    type Params = Vec<(String, String)>;
+
+    const METHODS: &'static [Method] = &[Method::Get, Method::Head, Method::Post];
+
+    fn handle_get(&mut self, _context: &Context, _path: &[String], _params: Self::Params) -> Result {
+        Ok(HttpOkay::Html(Box::new("page")))
+    }
+}
+
+/// Routes `/page` to `Page`, and answers `/about` itself.
+struct Site;
+
+impl Route for Site {
+    fn route(&mut self, context: &Context, path: &[String], callback: impl Callback) -> Result {
+        if path.first().is_some_and(|page| "page" == page) { return callback.handle_with(&mut Page); }
+        if let Some(response) = context.check_method(&[Method::Get, Method::Head, Method::Post])? { return Ok(response); }
+        Ok(HttpOkay::Html(Box::new("about")))
+    }
+}
+
+#[test]
+fn options() {
+    let mut client = TestClient::new(Site);
+    for path in ["/page", "/about"] {
+        let response = client.send(TestRequest::new(Method::Options, path));
+        assert_eq!(response.status, 204, "{}", path);
+        assert_eq!(response.header("Allow"), Some("GET, HEAD, POST, OPTIONS"), "{}", path);
+    }
+}
+
+#[test]
+fn method_not_allowed() {
+    let mut client = TestClient::new(Site);
+    for path in ["/page", "/about"] {
+        let response = client.send(TestRequest::delete(path));
+        assert_eq!(response.status, 405, "{}", path);
+        assert_eq!(response.header("Allow"), Some("GET, HEAD, POST, OPTIONS"), "{}", path);
+    }
+}
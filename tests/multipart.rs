@@ -0,0 +1,89 @@
+//! `multipart/form-data` request bodies.
+
+use std::io::{Read};
+
+use petite_http::{Context, Handle, HttpOkay, Method, Result, ServerBuilder, Upload};
+use petite_http::testing::{TestClient, TestRequest};
+
+/// Describes the form fields and uploads of `POST` requests.
+struct Form;
+
+impl Handle for Form {
+    type Params = Vec<(String, String)>;
+
+    const METHODS: &'static [Method] = &[Method::Post];
+
+    fn handle_get(&mut self, _context: &Context, _path: &[String], _params: Self::Params) -> Result {
+        Ok(HttpOkay::Empty)
+    }
+
+    fn handle_post(&mut self, _context: &Context, _path: &[String], params: Self::Params, uploads: Vec<Upload>) -> Result {
+        let mut text: Vec<String> = params.into_iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+        for mut upload in uploads {
+            let mut contents = String::new();
+            upload.file.read_to_string(&mut contents)?;
+            text.push(format!("{}:{}", upload.name, contents));
+        }
+        Ok(HttpOkay::Html(Box::new(text.join(" "))))
+    }
+}
+
+fn client() -> TestClient<Form> {
+    let builder = ServerBuilder::new("localhost").max_body_length(1000).max_uploads(2).log(|_| {});
+    TestClient::with_builder(builder, Form).unwrap()
+}
+
+/// A `POST` request with a multipart `body`.
+fn post(body: &str) -> TestRequest {
+    TestRequest::post("/").body("multipart/form-data; boundary=XYZ", body)
+}
+
+/// A well-formed multipart body with one field and one file.
+const BODY: &str = concat!(
+    "--XYZ\r\n",
+    "Content-Disposition: form-data; name=\"greeting\"\r\n\r\n",
+    "hello\r\n",
+    "--XYZ\r\n",
+    "Content-Disposition: form-data; name=\"upload\"; filename=\"a.txt\"\r\n",
+    "Content-Type: text/plain\r\n\r\n",
+    "file contents\r\n",
+    "--XYZ--\r\n",
+);
+
+#[test]
+fn well_formed() {
+    let response = client().send(post(BODY));
+    assert_eq!(response.status, 200);
+    assert_eq!(response.text(), "greeting=hello upload:file contents");
+}
+
+#[test]
+fn truncated() {
+    for length in [10, 60, 100, BODY.len() - 12] {
+        let response = client().send(post(&BODY[..length]));
+        assert_eq!(response.status, 400, "{:?}", &BODY[..length]);
+    }
+}
+
+#[test]
+fn missing_boundary() {
+    assert_eq!(client().send(post("hello")).status, 400);
+}
+
+#[test]
+fn too_long() {
+    let body = BODY.replace("hello", &"x".repeat(1000));
+    assert_eq!(client().send(post(&body)).status, 413);
+}
+
+#[test]
+fn too_many_uploads() {
+    let file = |n| format!(
+        "--XYZ\r\nContent-Disposition: form-data; name=\"file{0}\"; filename=\"{0}.txt\"\r\n\r\n{0}\r\n", n
+    );
+    let mut client = client();
+    let body = format!("{}{}--XYZ--\r\n", file(1), file(2));
+    assert_eq!(client.send(post(&body)).text(), "file1:1 file2:2");
+    let body = format!("{}{}{}--XYZ--\r\n", file(1), file(2), file(3));
+    assert_eq!(client.send(post(&body)).status, 413);
+}
@@ -0,0 +1,108 @@
+//! Running a server on a socket.
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{mpsc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration};
+
+use petite_http::{Callback, Context, HttpOkay, Result, Route, ServerBuilder, SharedRoute};
+
+/// A builder for a server on a free local port.
+fn builder() -> ServerBuilder {
+    let address = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    ServerBuilder::new(address.to_string()).handle_signals(false).log(|_| {})
+}
+
+/// Send a `GET` request for `path` to `address` and return the response.
+fn get(address: &str, path: &str) -> String {
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(stream, "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n", path, address).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+/// Waits until `expected` requests are being handled at once, and reports
+/// how many were.
+struct Rendezvous {
+    expected: usize,
+    active: Mutex<usize>,
+    changed: Condvar,
+}
+
+impl SharedRoute for Rendezvous {
+    fn route(&self, _context: &Context, _path: &[String], _callback: impl Callback) -> Result {
+        let mut active = self.active.lock().unwrap();
+        *active += 1;
+        self.changed.notify_all();
+        let (active, _) = self.changed.wait_timeout_while(
+            active, Duration::from_secs(5), |active| *active < self.expected
+        ).unwrap();
+        Ok(HttpOkay::Html(Box::new(format!("active={}", *active))))
+    }
+}
+
+#[test]
+fn spawn_pool() {
+    let expected = 3;
+    let router = Rendezvous {expected, active: Mutex::new(0), changed: Condvar::new()};
+    let handle = builder().workers(expected).build().unwrap().spawn_pool(router);
+    let address = handle.server_url().authority().to_owned();
+    let clients: Vec<_> = (0..expected).map(|_| {
+        let address = address.clone();
+        thread::spawn(move || get(&address, "/"))
+    }).collect();
+    for client in clients {
+        let response = client.join().unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 "), "{}", response);
+        assert!(response.ends_with("active=3"), "{}", response);
+    }
+    let router = handle.shutdown();
+    assert_eq!(*router.active.lock().unwrap(), expected);
+}
+
+/// Counts requests. The first one waits for a message.
+struct Gate {
+    count: usize,
+    started: mpsc::Sender<()>,
+    open: mpsc::Receiver<()>,
+}
+
+impl Route for Gate {
+    fn route(&mut self, _context: &Context, _path: &[String], _callback: impl Callback) -> Result {
+        self.count += 1;
+        if 1 == self.count {
+            self.started.send(()).unwrap();
+            self.open.recv().unwrap();
+        }
+        Ok(HttpOkay::Html(Box::new(format!("count={}", self.count))))
+    }
+}
+
+#[test]
+fn shutdown() {
+    let (started, on_started) = mpsc::channel();
+    let (open, on_open) = mpsc::channel();
+    let handle = builder().build().unwrap().spawn(Gate {count: 0, started, open: on_open});
+    let address = handle.server_url().authority().to_owned();
+    let first = {
+        let address = address.clone();
+        thread::spawn(move || get(&address, "/"))
+    };
+    on_started.recv().unwrap();
+    // The second request waits in the queue while the first is handled.
+    let second = {
+        let address = address.clone();
+        thread::spawn(move || get(&address, "/"))
+    };
+    thread::sleep(Duration::from_millis(200));
+    let shutdown = thread::spawn(move || handle.shutdown());
+    thread::sleep(Duration::from_millis(200));
+    open.send(()).unwrap();
+    let router = shutdown.join().unwrap();
+    assert_eq!(router.count, 2);
+    assert!(first.join().unwrap().ends_with("count=1"));
+    assert!(second.join().unwrap().ends_with("count=2"));
+}
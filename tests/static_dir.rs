@@ -0,0 +1,104 @@
+//! Serving a directory, and the paths that must not escape it.
+
+use std::fs;
+
+use petite_http::{HttpError, StaticDir};
+use petite_http::testing::{TestClient};
+use tempfile::{TempDir};
+
+/// A directory containing `root`, which is served, and `secret.txt`, which
+/// is not.
+///
+/// `root` contains `style.css`, `sub/page.html`, `.hidden` and `.git/config`.
+fn tree() -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("root");
+    fs::create_dir_all(root.join("sub")).unwrap();
+    fs::create_dir_all(root.join(".git")).unwrap();
+    fs::write(root.join("style.css"), "body {}").unwrap();
+    fs::write(root.join("sub/page.html"), "<p>page</p>").unwrap();
+    fs::write(root.join(".hidden"), "hidden").unwrap();
+    fs::write(root.join(".git/config"), "config").unwrap();
+    fs::write(dir.path().join("secret.txt"), "secret").unwrap();
+    dir
+}
+
+#[test]
+fn files() {
+    let dir = tree();
+    let mut client = TestClient::new(StaticDir::new(dir.path().join("root")));
+    let response = client.get("/style.css");
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Content-Type"), Some("text/css"));
+    assert_eq!(response.text(), "body {}");
+    let response = client.get("/sub/page.html");
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Content-Type"), Some("text/html"));
+    // Directories are not served.
+    for url in ["/", "/sub", "/sub/", "/missing.txt", "/style.css/x"] {
+        assert_eq!(client.get(url).status, 404, "{}", url);
+    }
+}
+
+#[test]
+fn parent_directory() {
+    let dir = tree();
+    let mut client = TestClient::new(StaticDir::new(dir.path().join("root")));
+    for url in [
+        "/../secret.txt",
+        "/%2E%2E/secret.txt",
+        "/sub/../../secret.txt",
+        "/..%2Fsecret.txt",
+        "/sub%2F..%2F..%2Fsecret.txt",
+        "/%2E%2E%5Csecret.txt",
+    ] {
+        assert_eq!(client.get(url).status, 404, "{}", url);
+    }
+}
+
+#[test]
+fn encoded_separator() {
+    let dir = tree();
+    let mut client = TestClient::new(StaticDir::new(dir.path().join("root")));
+    assert_eq!(client.get("/sub%2Fpage.html").status, 404);
+}
+
+#[test]
+fn hidden_files() {
+    let dir = tree();
+    let mut client = TestClient::new(StaticDir::new(dir.path().join("root")));
+    for url in ["/.hidden", "/.git/config", "/%2Ehidden"] {
+        assert_eq!(client.get(url).status, 404, "{}", url);
+    }
+}
+
+#[test]
+fn permissive_filter() {
+    let dir = tree();
+    let static_dir = StaticDir::new(dir.path().join("root")).filter(|_| true);
+    // The URL parser removes `..` segments, so ask directly.
+    for path in [&[".."][..], &["..", "secret.txt"], &["sub", "..", "..", "secret.txt"], &[".", "style.css"]] {
+        let path: Vec<String> = path.iter().map(|s| s.to_string()).collect();
+        assert!(matches!(static_dir.resolve(&path), Err(HttpError::NotFound)), "{:?}", path);
+    }
+    let mut client = TestClient::new(static_dir);
+    assert_eq!(client.get("/..%2Fsecret.txt").status, 404);
+    // Hidden files are now allowed.
+    assert_eq!(client.get("/.hidden").status, 200);
+}
+
+#[test]
+#[cfg(unix)]
+fn symbolic_links() {
+    use std::os::unix::fs::{symlink};
+    let dir = tree();
+    let root = dir.path().join("root");
+    symlink(dir.path().join("secret.txt"), root.join("secret.txt")).unwrap();
+    symlink(dir.path(), root.join("parent")).unwrap();
+    symlink(root.join("style.css"), root.join("link.css")).unwrap();
+    let mut client = TestClient::new(StaticDir::new(&root));
+    assert_eq!(client.get("/secret.txt").status, 404);
+    assert_eq!(client.get("/parent/secret.txt").status, 404);
+    // Links within the root directory are followed.
+    assert_eq!(client.get("/link.css").text(), "body {}");
+}
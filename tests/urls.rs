@@ -0,0 +1,32 @@
+//! The request target and the URL that the router sees.
+
+use petite_http::{Context, Handle, HttpOkay, Result, ServerBuilder};
+use petite_http::testing::{TestClient};
+
+/// Shows the URL of the request and its path segments.
+struct ShowUrl;
+
+impl Handle for ShowUrl {
+    type Params = Vec<(String, String)>;
+
+    fn handle_get(&mut self, context: &Context, path: &[String], _params: Self::Params) -> Result {
+        Ok(HttpOkay::Html(Box::new(format!("{} {:?}", context.url(), path))))
+    }
+}
+
+#[test]
+fn base_url() {
+    let builder = ServerBuilder::new("localhost").base_url("https://example.com/app/").log(|_| {});
+    let mut client = TestClient::with_builder(builder, ShowUrl).unwrap();
+    let response = client.get("/a/b%20c?x=1");
+    assert_eq!(response.status, 200);
+    assert_eq!(response.text(), "https://example.com/app/a/b%20c?x=1 [\"a\", \"b c\"]");
+}
+
+#[test]
+fn other_host() {
+    let mut client = TestClient::new(ShowUrl);
+    for url in ["//evil.example/x", "http://evil.example/x", "http://localhost:81/x"] {
+        assert_eq!(client.get(url).status, 400, "{}", url);
+    }
+}